@@ -0,0 +1,73 @@
+/*!
+Heap buffer with an aligned start address.
+
+The chunk buffers are allocated with this, so that they can be handed
+directly to the `O_DIRECT` file I/O.
+*/
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::ptr::NonNull;
+
+/// Heap buffer whose start address is aligned to `align` bytes.
+pub(crate) struct AlignedBuf {
+    ptr: NonNull<u8>,
+    len: usize,
+    align: usize,
+}
+
+impl AlignedBuf {
+    /// Allocates a zero-filled buffer of `len` bytes.
+    /// align is MUST power of 2.
+    pub(crate) fn zeroed(len: usize, align: usize) -> Self {
+        debug_assert!(align.is_power_of_two(), "align: {}", align);
+        if len == 0 {
+            return Self {
+                ptr: NonNull::dangling(),
+                len,
+                align,
+            };
+        }
+        let layout = Layout::from_size_align(len, align).expect("invalid buffer layout");
+        let ptr = unsafe { alloc_zeroed(layout) };
+        match NonNull::new(ptr) {
+            Some(ptr) => Self { ptr, len, align },
+            None => handle_alloc_error(layout),
+        }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            let layout = unsafe { Layout::from_size_align_unchecked(self.len, self.align) };
+            unsafe { dealloc(self.ptr.as_ptr(), layout) };
+        }
+    }
+}
+
+impl std::ops::Deref for AlignedBuf {
+    type Target = [u8];
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl std::ops::DerefMut for AlignedBuf {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl std::fmt::Debug for AlignedBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlignedBuf")
+            .field("len", &self.len)
+            .field("align", &self.align)
+            .finish()
+    }
+}
+
+// the buffer is owned uniquely, same as `Vec<u8>`.
+unsafe impl Send for AlignedBuf {}
+unsafe impl Sync for AlignedBuf {}
@@ -0,0 +1,316 @@
+/*!
+Append-only buffer for logs.
+
+[`RaAppender`] writes only at the end of the file. The appended bytes are
+combined in a tail buffer that starts at a chunk boundary, and the full
+chunks of it are written to the file in one large sequential write. The
+appended chunks do not go into the chunk cache, so they do not evict the
+cached chunks of the reads, and they are never read from the file.
+
+The bytes before the tail are read through the chunk cache, and the tail
+is read from the tail buffer, so the whole file is readable with
+`Read` and [`SmallRead`]. A write at other than the end is an error.
+
+# Examples
+
+```rust
+use rabuf::{RaAppender, SmallRead};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let path = "target/tmp/doc_test_append";
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open(path).unwrap();
+let mut ap = RaAppender::with_capacity("log", f, 4096, 4).unwrap();
+ap.write_all(b"record-1\n").unwrap();
+ap.write_all(b"record-2\n").unwrap();
+ap.seek(SeekFrom::Start(9)).unwrap();
+assert_eq!(ap.read_u8().unwrap(), b'r');
+assert!(ap.write_all(b"x").is_err());
+```
+*/
+use super::{seek_pos, FileSync, MaybeSlice, RaBuf, RaBufError, RaBufOp, SmallRead};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+/// The default number of chunks that are combined into a write.
+const DEFAULT_FLUSH_CHUNKS: usize = 16;
+
+/// Append-only buffer with a write-combining tail.
+///
+/// The tail is written on drop, but the error of it can not be returned.
+/// Call `flush()` before drop to see the errors. A failed write on drop
+/// is printed to stderr.
+#[derive(Debug)]
+pub struct RaAppender<T: Seek + Read + Write> {
+    /// The chunk cache for the bytes before the tail.
+    buf: RaBuf<T>,
+    /// The bytes from `tail_off` to the end, that are not written yet.
+    tail: Vec<u8>,
+    /// The start offset of the tail. it is a chunk boundary.
+    tail_off: u64,
+    /// The number of full chunks in the tail that triggers a write.
+    flush_chunks: usize,
+}
+
+impl<T: Seek + Read + Write> RaAppender<T> {
+    /// Creates a new RaAppender.
+    /// number of chunk: 16, chunk size: 4096
+    pub fn new(name: &str, file: T) -> Result<RaAppender<T>> {
+        Self::from_rabuf(RaBuf::new(name, file)?)
+    }
+    /// Creates a new RaAppender with the specified number of chunks.
+    /// chunk_size is MUST power of 2.
+    pub fn with_capacity(
+        name: &str,
+        file: T,
+        chunk_size: u32,
+        max_num_chunks: u16,
+    ) -> Result<RaAppender<T>> {
+        Self::from_rabuf(RaBuf::with_capacity(
+            name,
+            file,
+            chunk_size,
+            max_num_chunks,
+        )?)
+    }
+    fn from_rabuf(mut buf: RaBuf<T>) -> Result<RaAppender<T>> {
+        // the partial last chunk is read once, into the tail.
+        let end = buf.end;
+        let tail_off = end & buf.chunk_mask;
+        let mut tail = Vec::with_capacity(DEFAULT_FLUSH_CHUNKS * buf.chunk_size);
+        tail.resize((end - tail_off) as usize, 0u8);
+        buf.pos = tail_off;
+        buf.read_exact(&mut tail)?;
+        buf.invalidate_range(tail_off, tail.len() as u64, true)?;
+        buf.pos = 0;
+        Ok(Self {
+            buf,
+            tail,
+            tail_off,
+            flush_chunks: DEFAULT_FLUSH_CHUNKS,
+        })
+    }
+    /// Sets the number of full chunks in the tail that triggers a write.
+    /// The default is 16.
+    #[inline]
+    pub fn set_flush_chunks(&mut self, num_chunks: usize) {
+        self.flush_chunks = num_chunks.max(1);
+    }
+    /// Name for debugging
+    #[inline]
+    pub fn name(&self) -> String {
+        self.buf.name()
+    }
+    /// The length of the file, including the tail.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.tail_off + self.tail.len() as u64
+    }
+    /// Returns true if the file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Writes the full chunks of the tail, or the whole tail if `all` is true.
+    /// The partial last chunk stays in the tail.
+    fn write_tail_(&mut self, all: bool) -> Result<()> {
+        let full = self.tail.len() & self.buf.chunk_mask as usize;
+        let len = if all { self.tail.len() } else { full };
+        if len == 0 {
+            return Ok(());
+        }
+        let offset = self.tail_off;
+        let name = &self.buf.name;
+        let write_err = |err| RaBufError::new(name, RaBufOp::WriteBack, offset, len as u64, err);
+        self.buf
+            .file
+            .seek(SeekFrom::Start(offset))
+            .map_err(write_err)?;
+        self.buf
+            .file
+            .write_all(&self.tail[..len])
+            .map_err(write_err)?;
+        // the cached chunks over the old end are stale.
+        self.buf.invalidate_range(offset, len as u64, true)?;
+        if self.buf.end < offset + len as u64 {
+            self.buf.end = offset + len as u64;
+        }
+        self.tail.drain(..full);
+        self.tail_off += full as u64;
+        Ok(())
+    }
+    /// Returns true if `size` bytes from the current position are before the tail.
+    #[inline]
+    fn before_tail(&self, size: usize) -> bool {
+        self.buf.pos <= self.tail_off && size as u64 <= self.tail_off - self.buf.pos
+    }
+}
+
+impl<T: Seek + Read + Write> Read for RaAppender<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let pos = self.buf.pos;
+        if pos < self.tail_off {
+            let rest = self.tail_off - pos;
+            if (buf.len() as u64) > rest {
+                let rest = rest as usize;
+                return self.buf.read(&mut buf[..rest]);
+            }
+            return self.buf.read(buf);
+        }
+        if pos >= self.len() {
+            return Ok(0);
+        }
+        let st = (pos - self.tail_off) as usize;
+        let n = buf.len().min(self.tail.len() - st);
+        buf[..n].copy_from_slice(&self.tail[st..(st + n)]);
+        self.buf.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<T: Seek + Read + Write> Write for RaAppender<T> {
+    /// Appends the bytes. The current position MUST be the end.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let end = self.len();
+        if self.buf.pos != end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "rabuf \"{}\": append only, the position {} is not the end {}",
+                    self.buf.name, self.buf.pos, end
+                ),
+            ));
+        }
+        self.tail.extend_from_slice(buf);
+        self.buf.pos += buf.len() as u64;
+        if self.tail.len() >= self.flush_chunks * self.buf.chunk_size {
+            self.write_tail_(false)?;
+        }
+        Ok(buf.len())
+    }
+    /// Writes the whole tail to the file.
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        self.write_tail_(true)?;
+        self.buf.file.flush()
+    }
+}
+
+impl<T: Seek + Read + Write> Seek for RaAppender<T> {
+    /// Seeks to the position. The position beyond the end is allowed,
+    /// but the reads from there return no bytes, and the writes fail.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.buf.pos = seek_pos(self.buf.pos, self.len(), pos)?;
+        Ok(self.buf.pos)
+    }
+}
+
+impl<T: Seek + Read + Write + FileSync> FileSync for RaAppender<T> {
+    /// Writes the tail and call `sync_all()` of the underlying file.
+    #[inline]
+    fn sync_all(&mut self) -> Result<()> {
+        self.flush()?;
+        self.buf.sync_all()
+    }
+    /// Writes the tail and call `sync_data()` of the underlying file.
+    #[inline]
+    fn sync_data(&mut self) -> Result<()> {
+        self.flush()?;
+        self.buf.sync_data()
+    }
+}
+
+impl<T: Seek + Read + Write> Drop for RaAppender<T> {
+    /// Write the tail to disk before closing the file.
+    fn drop(&mut self) {
+        if let Err(err) = self.write_tail_(true) {
+            eprintln!("rabuf \"{}\": flush on drop failed: {}", self.buf.name, err);
+        }
+    }
+}
+
+// the reads over the tail go through `read_exact()`.
+impl<T: Seek + Read + Write> SmallRead for RaAppender<T> {
+    #[inline]
+    fn read_u8(&mut self) -> Result<u8> {
+        if self.before_tail(1) {
+            self.buf.read_u8()
+        } else {
+            let mut buf = [0u8; 1];
+            self.read_exact(&mut buf)?;
+            Ok(buf[0])
+        }
+    }
+    #[inline]
+    fn read_u16_le(&mut self) -> Result<u16> {
+        if self.before_tail(2) {
+            self.buf.read_u16_le()
+        } else {
+            let mut buf = [0u8; 2];
+            self.read_exact(&mut buf)?;
+            Ok(u16::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_u32_le(&mut self) -> Result<u32> {
+        if self.before_tail(4) {
+            self.buf.read_u32_le()
+        } else {
+            let mut buf = [0u8; 4];
+            self.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_u64_le(&mut self) -> Result<u64> {
+        if self.before_tail(8) {
+            self.buf.read_u64_le()
+        } else {
+            let mut buf = [0u8; 8];
+            self.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_max_8_bytes(&mut self, size: usize) -> Result<u64> {
+        debug_assert!(size <= 8, "size: {} <= 8", size);
+        if self.before_tail(size) {
+            self.buf.read_max_8_bytes(size)
+        } else {
+            let mut buf = [0u8; 8];
+            self.read_exact(&mut buf[..size])?;
+            Ok(u64::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_exact_small(&mut self, buf: &mut [u8]) -> Result<()> {
+        if self.before_tail(buf.len()) {
+            self.buf.read_exact_small(buf)
+        } else {
+            self.read_exact(buf)
+        }
+    }
+    #[inline]
+    fn read_exact_maybeslice(&mut self, size: usize) -> Result<MaybeSlice<'_>> {
+        if self.before_tail(size) {
+            return self.buf.read_exact_maybeslice(size);
+        }
+        let pos = self.buf.pos;
+        let len = self.len();
+        // do not allocate a huge buffer for a broken size.
+        if pos > len || size as u64 > len - pos {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        if pos >= self.tail_off {
+            // borrows the tail buffer.
+            let st = (pos - self.tail_off) as usize;
+            self.buf.pos += size as u64;
+            return Ok(MaybeSlice::Slice(&self.tail[st..(st + size)]));
+        }
+        let mut buf = vec![0u8; size];
+        self.read_exact(&mut buf)?;
+        Ok(MaybeSlice::Buffer(buf))
+    }
+}
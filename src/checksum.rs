@@ -0,0 +1,117 @@
+/*!
+Checksum of a range through the chunk cache.
+
+[`RaBuf::checksum()`] streams over the chunks of a range with
+[`RaBuf::chunks()`], so the cached chunks are used in place with their
+dirty data, and the result is of the logical contents that `Read` returns.
+
+# Examples
+
+```rust
+use rabuf::{Algorithm, BufFile};
+use std::io::Write;
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let path = "target/tmp/doc_test_checksum";
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open(path).unwrap();
+let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+bf.write_all(b"123456789").unwrap();
+assert_eq!(bf.checksum(.., Algorithm::Crc32c).unwrap(), 0xe306_9283);
+```
+*/
+use super::RaBuf;
+use std::io::{Read, Result, Seek, Write};
+use std::ops::RangeBounds;
+
+/// The algorithm of [`RaBuf::checksum()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// CRC-32C (Castagnoli), in the lower 32 bits.
+    Crc32c,
+    /// 64-bit FNV-1a hash. it is not cryptographic.
+    Fnv1a64,
+}
+
+impl<T: Seek + Read + Write> RaBuf<T> {
+    /// Returns the checksum of `range`, clamped to the end.
+    ///
+    /// The dirty data is included without writing it.
+    /// The current position is not changed.
+    pub fn checksum<R: RangeBounds<u64>>(&mut self, range: R, algorithm: Algorithm) -> Result<u64> {
+        let mut state = State::new(algorithm);
+        let mut iter = self.chunks(range);
+        while let Some(r) = iter.next_chunk() {
+            let (_, data) = r?;
+            state.update(data);
+        }
+        Ok(state.finish())
+    }
+}
+
+/// The running state of a checksum.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Crc32c(u32),
+    Fnv1a64(u64),
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl State {
+    fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Crc32c => State::Crc32c(!0),
+            Algorithm::Fnv1a64 => State::Fnv1a64(FNV_OFFSET_BASIS),
+        }
+    }
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            State::Crc32c(crc) => {
+                let mut c = *crc;
+                for &b in data {
+                    c = CRC32C_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8);
+                }
+                *crc = c;
+            }
+            State::Fnv1a64(hash) => {
+                let mut h = *hash;
+                for &b in data {
+                    h = (h ^ b as u64).wrapping_mul(FNV_PRIME);
+                }
+                *hash = h;
+            }
+        }
+    }
+    fn finish(self) -> u64 {
+        match self {
+            State::Crc32c(crc) => !crc as u64,
+            State::Fnv1a64(hash) => hash,
+        }
+    }
+}
+
+/// The table of CRC-32C, the reflected polynomial 0x82f63b78.
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0x82f6_3b78 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
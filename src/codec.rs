@@ -0,0 +1,276 @@
+/*!
+Typed encode and decode of values.
+
+[`RaEncode`] and [`RaDecode`] write and read a value as little endian bytes.
+They are implemented for the primitives, arrays, tuples, `Option`, `Vec`
+and `String`, and can be implemented for the structs by encoding the fields
+in order.
+
+A type with `FIXED_SIZE` is read and written by `read_value()` and
+`write_value()` of [`RaBuf`](super::RaBuf) directly from and into the
+current chunk, with one bounds check, when it fits in the chunk.
+
+The encoding:
+- integers and floats: little endian.
+- `bool`: one byte, 0 or 1.
+- arrays and tuples: the elements in order.
+- `Option<T>`: one byte tag 0 for `None`, or 1 followed by the value.
+- `Vec<T>` and `String`: the length as `u64`, followed by the elements or the UTF-8 bytes.
+
+# Examples
+
+```rust
+use rabuf::{BufFile, RaDecode, RaEncode};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    key: u64,
+    name: String,
+}
+
+impl RaEncode for Entry {
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        self.key.encode(w)?;
+        self.name.encode(w)
+    }
+}
+
+impl RaDecode for Entry {
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        Ok(Entry {
+            key: RaDecode::decode(r)?,
+            name: RaDecode::decode(r)?,
+        })
+    }
+}
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open("target/tmp/doc_test_codec").unwrap();
+let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+bf.write_value(&(1u32, [2u16; 3])).unwrap();
+bf.write_value(&Entry { key: 7, name: "seven".to_string() }).unwrap();
+bf.seek(SeekFrom::Start(0)).unwrap();
+assert_eq!(bf.read_value::<(u32, [u16; 3])>().unwrap(), (1, [2; 3]));
+let entry: Entry = bf.read_value().unwrap();
+assert_eq!(entry.name, "seven");
+```
+*/
+use std::convert::TryFrom;
+use std::io::{Read, Result, Write};
+
+/// Encodes a value into bytes.
+pub trait RaEncode {
+    /// The encoded size in bytes, if it is the same for all values.
+    const FIXED_SIZE: Option<usize> = None;
+    /// Writes the encoded bytes.
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()>;
+}
+
+/// Decodes a value from bytes.
+pub trait RaDecode: Sized {
+    /// The encoded size in bytes, if it is the same for all values.
+    const FIXED_SIZE: Option<usize> = None;
+    /// Reads the encoded bytes.
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self>;
+}
+
+/// Adds the fixed sizes, or returns None if any of them is not fixed.
+pub(crate) const fn add_fixed_size(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        _ => None,
+    }
+}
+
+fn invalid_data(msg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+macro_rules! impl_codec_num {
+    ($($t:ty),*) => {$(
+        impl RaEncode for $t {
+            const FIXED_SIZE: Option<usize> = Some(std::mem::size_of::<$t>());
+            #[inline]
+            fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+        }
+        impl RaDecode for $t {
+            const FIXED_SIZE: Option<usize> = Some(std::mem::size_of::<$t>());
+            #[inline]
+            fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+                let mut buf = [0u8; std::mem::size_of::<$t>()];
+                r.read_exact(&mut buf)?;
+                Ok(<$t>::from_le_bytes(buf))
+            }
+        }
+    )*};
+}
+
+impl_codec_num!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl RaEncode for bool {
+    const FIXED_SIZE: Option<usize> = Some(1);
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        (*self as u8).encode(w)
+    }
+}
+
+impl RaDecode for bool {
+    const FIXED_SIZE: Option<usize> = Some(1);
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        match u8::decode(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            x => Err(invalid_data(format!("invalid bool: {}", x))),
+        }
+    }
+}
+
+impl<T: RaEncode, const N: usize> RaEncode for [T; N] {
+    const FIXED_SIZE: Option<usize> = match T::FIXED_SIZE {
+        Some(n) => Some(n * N),
+        None => None,
+    };
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        for a in self.iter() {
+            a.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: RaDecode, const N: usize> RaDecode for [T; N] {
+    const FIXED_SIZE: Option<usize> = match T::FIXED_SIZE {
+        Some(n) => Some(n * N),
+        None => None,
+    };
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        let mut vec = Vec::with_capacity(N);
+        for _ in 0..N {
+            vec.push(T::decode(r)?);
+        }
+        match <[T; N]>::try_from(vec) {
+            Ok(x) => Ok(x),
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+macro_rules! impl_codec_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t: RaEncode),+> RaEncode for ($($t,)+) {
+            const FIXED_SIZE: Option<usize> = {
+                let size = Some(0);
+                $(let size = add_fixed_size(size, $t::FIXED_SIZE);)+
+                size
+            };
+            #[inline]
+            fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+                $(self.$i.encode(w)?;)+
+                Ok(())
+            }
+        }
+        impl<$($t: RaDecode),+> RaDecode for ($($t,)+) {
+            const FIXED_SIZE: Option<usize> = {
+                let size = Some(0);
+                $(let size = add_fixed_size(size, $t::FIXED_SIZE);)+
+                size
+            };
+            #[inline]
+            fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+                Ok(($($t::decode(r)?,)+))
+            }
+        }
+    };
+}
+
+impl_codec_tuple!(A 0);
+impl_codec_tuple!(A 0, B 1);
+impl_codec_tuple!(A 0, B 1, C 2);
+impl_codec_tuple!(A 0, B 1, C 2, D 3);
+impl_codec_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_codec_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_codec_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_codec_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl<T: RaEncode> RaEncode for Option<T> {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        match self {
+            None => 0u8.encode(w),
+            Some(x) => {
+                1u8.encode(w)?;
+                x.encode(w)
+            }
+        }
+    }
+}
+
+impl<T: RaDecode> RaDecode for Option<T> {
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        match u8::decode(r)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(r)?)),
+            x => Err(invalid_data(format!("invalid option tag: {}", x))),
+        }
+    }
+}
+
+/// Reads the length of `Vec` and `String`.
+fn decode_len<R: Read + ?Sized>(r: &mut R) -> Result<usize> {
+    let len = u64::decode(r)?;
+    usize::try_from(len).map_err(|_| invalid_data(format!("too large length: {}", len)))
+}
+
+/// The upper limit of the capacity that is allocated before reading the elements,
+/// not to allocate a huge buffer with a broken length.
+const MAX_PREALLOC: usize = 4096;
+
+impl<T: RaEncode> RaEncode for Vec<T> {
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        (self.len() as u64).encode(w)?;
+        for a in self.iter() {
+            a.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: RaDecode> RaDecode for Vec<T> {
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        let len = decode_len(r)?;
+        let mut vec = Vec::with_capacity(len.min(MAX_PREALLOC));
+        for _ in 0..len {
+            vec.push(T::decode(r)?);
+        }
+        Ok(vec)
+    }
+}
+
+impl RaEncode for String {
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        (self.len() as u64).encode(w)?;
+        w.write_all(self.as_bytes())
+    }
+}
+
+impl RaDecode for String {
+    fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        let len = decode_len(r)?;
+        let mut vec = Vec::with_capacity(len.min(MAX_PREALLOC));
+        r.take(len as u64).read_to_end(&mut vec)?;
+        if vec.len() < len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        String::from_utf8(vec).map_err(|e| invalid_data(e.to_string()))
+    }
+}
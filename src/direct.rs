@@ -0,0 +1,323 @@
+/*!
+Direct I/O file that bypasses the kernel page cache.
+
+With `O_DIRECT`, every read and write must start at a block aligned
+offset, have a block aligned length, and use a block aligned memory
+buffer. [`DirectFile`] keeps these rules: the aligned part of a request
+is passed to the file as is, and the unaligned head or tail is done
+through an aligned bounce buffer, with read-modify-write of the partial
+blocks. After writing the final partial block, the file is truncated
+back to its logical length.
+
+[`DirectBufFile`] allocates the chunk buffers aligned to the block size,
+so that the chunks are read and written without the bounce buffer,
+except the chunk that contains the end of the file.
+
+# Examples
+
+```rust
+use rabuf::{DirectBufFile, DirectFile};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let path = "target/tmp/doc_test_direct";
+
+let mut opts = OpenOptions::new();
+opts.create(true).truncate(true).read(true).write(true);
+# let r = DirectFile::open(&mut opts, path, 4096);
+# if r.is_err() { return; }
+let f = DirectFile::open(&mut opts, path, 4096).unwrap();
+let mut bf = DirectBufFile::with_direct("tes", f, 4096, 4).unwrap();
+bf.write_all(b"ABCEDFG\nhijklmn\n").unwrap();
+bf.seek(SeekFrom::Start(0)).unwrap();
+let mut br = vec![0u8; 16];
+bf.read_exact(&mut br).unwrap();
+assert_eq!(&br, b"ABCEDFG\nhijklmn\n");
+```
+*/
+use super::aligned::AlignedBuf;
+use super::{
+    read_full, seek_pos, Advice, FileAdvise, FileCopyRange, FilePunchHole, FileSeekData,
+    FileSetLen, FileSync, RaBuf,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Buffered direct I/O file for random access.
+pub type DirectBufFile = RaBuf<DirectFile>;
+
+impl DirectBufFile {
+    /// Creates a new DirectBufFile with the specified number of chunks.
+    /// The chunk buffers are aligned to the block size of the file.
+    /// chunk_size is MUST power of 2, and MUST be a multiple of the block size.
+    pub fn with_direct(
+        name: &str,
+        file: DirectFile,
+        chunk_size: u32,
+        max_num_chunks: u16,
+    ) -> Result<DirectBufFile> {
+        let block_size = file.block_size();
+        if chunk_size as usize % block_size != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "chunk size {} is not a multiple of block size {}",
+                    chunk_size, block_size
+                ),
+            ));
+        }
+        let mut rabuf = Self::with_capacity(name, file, chunk_size, max_num_chunks)?;
+        rabuf.buf_align = block_size;
+        Ok(rabuf)
+    }
+}
+
+/// File with block aligned I/O, for `O_DIRECT`.
+#[derive(Debug)]
+pub struct DirectFile {
+    file: File,
+    /// The logical block size. it is a power of 2.
+    block_size: usize,
+    /// The current position of the file.
+    pos: u64,
+    /// The logical length of the file.
+    len: u64,
+    /// The aligned buffer for the unaligned head and tail of requests.
+    bounce: Option<AlignedBuf>,
+}
+
+impl DirectFile {
+    /// Opens a file with `O_DIRECT` added to `options` on linux,
+    /// or with `F_NOCACHE` set by `fcntl()` on macos.
+    /// On the others, it is an error of `ErrorKind::Unsupported`.
+    /// block_size is MUST power of 2.
+    pub fn open<P: AsRef<Path>>(
+        options: &mut OpenOptions,
+        path: P,
+        block_size: usize,
+    ) -> Result<DirectFile> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_DIRECT);
+            let file = options.open(path)?;
+            Self::from_file(file, block_size)
+        }
+        #[cfg(target_os = "macos")]
+        {
+            use std::os::unix::io::AsRawFd;
+            let file = options.open(path)?;
+            let r = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) };
+            if r == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Self::from_file(file, block_size)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            let _ = (options, path.as_ref(), block_size);
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "direct I/O is not supported on this platform",
+            ))
+        }
+    }
+    /// Wraps a file that is already opened.
+    /// block_size is MUST power of 2.
+    pub fn from_file(mut file: File, block_size: usize) -> Result<DirectFile> {
+        if !block_size.is_power_of_two() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("block size {} is not a power of 2", block_size),
+            ));
+        }
+        let len = file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            file,
+            block_size,
+            pos: 0,
+            len,
+            bounce: None,
+        })
+    }
+    /// The logical block size.
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+    /// Unwraps the inner file.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+    /// The length of the block aligned prefix of the request,
+    /// which can be passed to the file as is.
+    #[inline]
+    fn aligned_len(&self, ptr: *const u8, len: usize) -> usize {
+        let mask = self.block_size - 1;
+        if (ptr as usize) & mask == 0 && (self.pos as usize) & mask == 0 {
+            len & !mask
+        } else {
+            0
+        }
+    }
+    /// The block aligned range that covers the request.
+    #[inline]
+    fn block_range(&self, len: usize) -> (u64, usize) {
+        let bs = self.block_size as u64;
+        let st = self.pos & !(bs - 1);
+        let ed = (self.pos + len as u64 + bs - 1) & !(bs - 1);
+        (st, (ed - st) as usize)
+    }
+}
+
+/// Returns the bounce buffer which has at least `size` bytes.
+fn bounce_buf(bounce: &mut Option<AlignedBuf>, size: usize, align: usize) -> &mut [u8] {
+    let enough = matches!(bounce, Some(buf) if buf.len() >= size);
+    if !enough {
+        *bounce = Some(AlignedBuf::zeroed(size, align));
+    }
+    match bounce {
+        Some(buf) => &mut buf[..size],
+        None => unreachable!(),
+    }
+}
+
+impl Read for DirectFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        let aligned_len = self.aligned_len(buf.as_ptr(), buf.len());
+        if aligned_len > 0 {
+            self.file.seek(SeekFrom::Start(self.pos))?;
+            let n = self.file.read(&mut buf[..aligned_len])?;
+            self.pos += n as u64;
+            return Ok(n);
+        }
+        let (st, size) = self.block_range(buf.len());
+        let skip = (self.pos - st) as usize;
+        let bounce = bounce_buf(&mut self.bounce, size, self.block_size);
+        self.file.seek(SeekFrom::Start(st))?;
+        let got = read_full(&mut self.file, bounce)?;
+        if got <= skip {
+            return Ok(0);
+        }
+        let n = buf.len().min(got - skip);
+        buf[..n].copy_from_slice(&bounce[skip..(skip + n)]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Write for DirectFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let aligned_len = self.aligned_len(buf.as_ptr(), buf.len());
+        if aligned_len > 0 {
+            self.file.seek(SeekFrom::Start(self.pos))?;
+            let n = self.file.write(&buf[..aligned_len])?;
+            self.pos += n as u64;
+            if self.len < self.pos {
+                self.len = self.pos;
+            }
+            return Ok(n);
+        }
+        let bs = self.block_size;
+        let (st, size) = self.block_range(buf.len());
+        let skip = (self.pos - st) as usize;
+        let tail_st = st + (size - bs) as u64;
+        let read_head = skip != 0 && st < self.len;
+        let read_tail =
+            (skip + buf.len()) % bs != 0 && tail_st < self.len && !(read_head && tail_st == st);
+        //
+        let bounce = bounce_buf(&mut self.bounce, size, bs);
+        bounce.fill(0u8);
+        // read-modify-write of the partial blocks
+        if read_head {
+            self.file.seek(SeekFrom::Start(st))?;
+            read_full(&mut self.file, &mut bounce[..bs])?;
+        }
+        if read_tail {
+            self.file.seek(SeekFrom::Start(tail_st))?;
+            read_full(&mut self.file, &mut bounce[(size - bs)..])?;
+        }
+        bounce[skip..(skip + buf.len())].copy_from_slice(buf);
+        self.file.seek(SeekFrom::Start(st))?;
+        self.file.write_all(bounce)?;
+        //
+        self.pos += buf.len() as u64;
+        if self.len < self.pos {
+            self.len = self.pos;
+        }
+        if st + size as u64 > self.len {
+            // the padding of the final block is over the end.
+            self.file.set_len(self.len)?;
+        }
+        Ok(buf.len())
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for DirectFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.pos = seek_pos(self.pos, self.len, pos)?;
+        Ok(self.pos)
+    }
+}
+
+impl FileSetLen for DirectFile {
+    fn set_len(&mut self, size: u64) -> Result<()> {
+        self.file.set_len(size)?;
+        self.len = size;
+        Ok(())
+    }
+}
+
+impl FileSync for DirectFile {
+    #[inline]
+    fn sync_all(&mut self) -> Result<()> {
+        self.file.sync_all()
+    }
+    #[inline]
+    fn sync_data(&mut self) -> Result<()> {
+        self.file.sync_data()
+    }
+    #[inline]
+    fn sync_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        self.file.sync_range(offset, len)
+    }
+}
+
+impl FileCopyRange for DirectFile {}
+
+impl FilePunchHole for DirectFile {
+    #[inline]
+    fn punch_hole(&mut self, offset: u64, len: u64) -> Result<bool> {
+        self.file.punch_hole(offset, len)
+    }
+}
+
+impl FileSeekData for DirectFile {
+    #[inline]
+    fn next_data(&mut self, offset: u64) -> Result<Option<u64>> {
+        self.file.next_data(offset)
+    }
+    #[inline]
+    fn next_hole(&mut self, offset: u64) -> Result<Option<u64>> {
+        self.file.next_hole(offset)
+    }
+}
+
+impl FileAdvise for DirectFile {
+    #[inline]
+    fn advise(&mut self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        self.file.advise(offset, len, advice)
+    }
+}
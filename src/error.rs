@@ -0,0 +1,152 @@
+/*!
+Error with the context of the buffer.
+
+The errors of the underlying file are wrapped in [`RaBufError`], which
+carries the name of the buffer, the operation, and the range of the file.
+The public API returns [`std::io::Error`] that contains it, so it can be
+taken out with `get_ref()` and `downcast_ref()`, or converted back with
+`RaBufError::from()`.
+
+```rust
+use rabuf::{RaBufError, RaBufOp};
+
+let err = std::io::Error::from(RaBufError::new(
+    "tes",
+    RaBufOp::Load,
+    4096,
+    4096,
+    std::io::ErrorKind::UnexpectedEof.into(),
+));
+assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+let err = RaBufError::from(err);
+assert_eq!(err.name(), "tes");
+assert_eq!(err.op(), RaBufOp::Load);
+assert_eq!(err.offset(), 4096);
+```
+*/
+use std::fmt;
+use std::io;
+
+/// The operation on the underlying file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaBufOp {
+    /// Loading a chunk from the file.
+    Load,
+    /// Writing a dirty chunk back to the file.
+    WriteBack,
+    /// Truncating or extending the file.
+    SetLen,
+    /// Synchronizing the file to disk.
+    Sync,
+    /// Copying a range in the kernel with `copy_file_range()`.
+    CopyRange,
+    /// Deallocating a range with `fallocate()`.
+    PunchHole,
+    /// The others, for the errors converted from a bare `io::Error`.
+    Other,
+}
+
+impl fmt::Display for RaBufOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RaBufOp::Load => "load",
+            RaBufOp::WriteBack => "write-back",
+            RaBufOp::SetLen => "set_len",
+            RaBufOp::Sync => "sync",
+            RaBufOp::CopyRange => "copy_file_range",
+            RaBufOp::PunchHole => "punch_hole",
+            RaBufOp::Other => "io",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Error of the underlying file with the name of the buffer, the operation, and the range.
+#[derive(Debug)]
+pub struct RaBufError {
+    name: String,
+    op: RaBufOp,
+    offset: u64,
+    len: u64,
+    source: io::Error,
+}
+
+impl RaBufError {
+    /// Creates a new error.
+    pub fn new(name: &str, op: RaBufOp, offset: u64, len: u64, source: io::Error) -> Self {
+        Self {
+            name: name.to_string(),
+            op,
+            offset,
+            len,
+            source,
+        }
+    }
+    /// The name of the buffer.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The operation that failed.
+    #[inline]
+    pub fn op(&self) -> RaBufOp {
+        self.op
+    }
+    /// The offset of the range in the file.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// The length of the range in bytes.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    /// Returns true if the range is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// The error kind of the underlying error.
+    #[inline]
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+    /// Unwraps the underlying error.
+    pub fn into_inner(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for RaBufError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rabuf \"{}\": {} failed at offset {} len {}: {}",
+            self.name, self.op, self.offset, self.len, self.source
+        )
+    }
+}
+
+impl std::error::Error for RaBufError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<RaBufError> for io::Error {
+    fn from(err: RaBufError) -> io::Error {
+        io::Error::new(err.kind(), err)
+    }
+}
+
+impl From<io::Error> for RaBufError {
+    /// Takes out the `RaBufError` in the error, or wraps a bare error with `RaBufOp::Other`.
+    fn from(err: io::Error) -> RaBufError {
+        if err.get_ref().map(|e| e.is::<RaBufError>()) == Some(true) {
+            let inner = err.into_inner().unwrap();
+            return *inner.downcast::<RaBufError>().unwrap();
+        }
+        RaBufError::new("", RaBufOp::Other, 0, 0, err)
+    }
+}
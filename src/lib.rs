@@ -65,8 +65,9 @@ let bw = b"abcdefg\nHIJKLMN\n";
 }
 ```
 */
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{Read, Result, Seek, SeekFrom, Write};
+use std::io::{BufRead, Read, Result, Seek, SeekFrom, Write};
 
 #[cfg(feature = "buf_hash_turbo")]
 use std::collections::HashMap;
@@ -77,8 +78,44 @@ use std::hash::BuildHasherDefault;
 #[cfg(feature = "buf_myhash")]
 use std::hash::Hasher;
 
+mod aligned;
+use aligned::AlignedBuf;
+
+pub mod append;
+pub use append::RaAppender;
+
+pub mod checksum;
+pub use checksum::Algorithm;
+
+pub mod codec;
+pub use codec::{RaDecode, RaEncode};
+
+pub mod direct;
+pub use direct::{DirectBufFile, DirectFile};
+
+pub mod error;
+pub use error::{RaBufError, RaBufOp};
+
 pub mod maybe;
-pub use maybe::MaybeSlice;
+pub use maybe::{MaybeSlice, MaybeSlices};
+
+pub mod prefixed;
+pub use prefixed::LenWidth;
+
+pub mod reader;
+pub use reader::RaReader;
+
+pub mod record;
+pub use record::{Record, RecordFile, RecordLayout};
+
+pub mod region;
+pub use region::RegionFile;
+
+pub mod scan;
+pub use scan::RaChunks;
+
+pub mod segment;
+pub use segment::{SegmentedBufFile, SegmentedFile};
 
 /// Buffered File for ramdom access.
 pub type BufFile = RaBuf<File>;
@@ -107,29 +144,31 @@ pub trait FileSetLen {
     fn set_len(&mut self, size: u64) -> Result<()>;
 }
 
-impl FileSetLen for BufFile {
+impl FileSetLen for File {
+    /// ref. [`std::io::File.set_len()`](https://doc.rust-lang.org/std/fs/struct.File.html#method.set_len)
+    #[inline]
+    fn set_len(&mut self, size: u64) -> Result<()> {
+        File::set_len(self, size)
+    }
+}
+
+impl<T: Seek + Read + Write + FileSetLen> FileSetLen for RaBuf<T> {
     /// Truncates or extends the underlying file, updating the size of this file to become size.
     /// ref. [`std::io::File.set_len()`](https://doc.rust-lang.org/std/fs/struct.File.html#method.set_len)
     fn set_len(&mut self, size: u64) -> Result<()> {
+        self.check_poisoned_()?;
         if self.end >= size {
-            // shrink bunks
-            for i in 0..self.chunks.len() {
-                #[cfg(feature = "buf_debug")]
-                let chunk = &self.chunks[i];
-                #[cfg(not(feature = "buf_debug"))]
-                let chunk = unsafe { &*self.chunks.as_ptr().add(i) };
-                //
-                if chunk.offset + chunk.data.len() as u64 >= size {
-                    // data end is over the new end
-                    // nothing todo
-                } else if chunk.offset >= size {
-                    // chunk start is over the new end
-                    self.map.remove(&chunk.offset);
-                    self.fetch_cache = None;
-                    #[cfg(not(feature = "buf_overf_rem_all"))]
-                    {
-                        self.chunks[i].uses = 0;
-                    }
+            // shrink bunks: evicts the chunks past the new end, and zeros the tail
+            // of the chunk over it, so that a later extend reads zeros.
+            let mut i = self.chunks.len();
+            while i > 0 {
+                i -= 1;
+                let offset = self.chunks[i].offset;
+                if offset >= size {
+                    self.remove_chunk_(i);
+                } else if offset + self.chunks[i].data.len() as u64 > size {
+                    let st = (size - offset) as usize;
+                    self.chunks[i].data[st..].fill(0);
                 }
             }
         }
@@ -137,13 +176,15 @@ impl FileSetLen for BufFile {
         if self.end < self.pos {
             self.pos = self.end
         }
-        self.file.set_len(size)?;
+        self.file
+            .set_len(size)
+            .map_err(|err| RaBufError::new(&self.name, RaBufOp::SetLen, size, 0, err))?;
         //
         Ok(())
     }
 }
 
-impl Seek for BufFile {
+impl<T: Seek + Read + Write + FileSetLen> Seek for RaBuf<T> {
     #[inline]
     fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
         let new_pos = match pos {
@@ -173,28 +214,344 @@ impl Seek for BufFile {
     }
 }
 
+/// Access pattern hints for a range, similar to `madvise()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advice {
+    /// No special treatment. it clears the hints of the range.
+    Normal,
+    /// Accessed sequentially. the next chunks are read ahead,
+    /// and the chunks are evicted before the others.
+    Sequential,
+    /// Accessed in random order. the chunks are not read ahead.
+    Random,
+    /// Accessed in the near future. the chunks are loaded now.
+    WillNeed,
+    /// Not accessed in the near future. the chunks are written back and dropped now.
+    DontNeed,
+    /// Accessed only once. the chunks are evicted before the others.
+    NoReuse,
+}
+
+/// Access pattern hints for the underlying file.
+pub trait FileAdvise {
+    /// Advises the access pattern of the range.
+    /// `len` zero means up to the end of the file, as `posix_fadvise()`.
+    fn advise(&mut self, offset: u64, len: u64, advice: Advice) -> Result<()>;
+}
+
+impl FileAdvise for File {
+    /// Call `posix_fadvise()` on linux, and nothing todo on the others.
+    fn advise(&mut self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+            let advice = match advice {
+                Advice::Normal => libc::POSIX_FADV_NORMAL,
+                Advice::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+                Advice::Random => libc::POSIX_FADV_RANDOM,
+                Advice::WillNeed => libc::POSIX_FADV_WILLNEED,
+                Advice::DontNeed => libc::POSIX_FADV_DONTNEED,
+                Advice::NoReuse => libc::POSIX_FADV_NOREUSE,
+            };
+            // zero means up to the end of the file, and so does a length over off_t.
+            let len = if len > libc::off_t::MAX as u64 {
+                0
+            } else {
+                len
+            };
+            let r = unsafe {
+                libc::posix_fadvise(
+                    self.as_raw_fd(),
+                    offset as libc::off_t,
+                    len as libc::off_t,
+                    advice,
+                )
+            };
+            if r != 0 {
+                return Err(std::io::Error::from_raw_os_error(r));
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (offset, len, advice);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Seek + Read + Write + FileAdvise> FileAdvise for RaBuf<T> {
+    /// Apply the hints to the chunk cache, and forward them to the underlying file.
+    fn advise(&mut self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        self.advise_cache_(offset, len, advice)?;
+        self.file.advise(offset, len, advice)
+    }
+}
+
 /// File syncronization include OS-internal metadata to disk.
 pub trait FileSync {
     /// Attempts to sync all OS-internal metadata to disk.
     fn sync_all(&mut self) -> Result<()>;
     /// This function is similar to sync_all, except that it might not synchronize file metadata to the filesystem.
     fn sync_data(&mut self) -> Result<()>;
+    /// This function is similar to sync_data, except that it synchronizes only the range.
+    /// `len` zero means up to the end of the file, as `sync_file_range()`.
+    /// The default calls `sync_data()`.
+    fn sync_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        let _ = (offset, len);
+        self.sync_data()
+    }
+}
+
+impl FileSync for File {
+    /// ref. [`std::io::File.sync_all()`](https://doc.rust-lang.org/std/fs/struct.File.html#method.sync_all)
+    #[inline]
+    fn sync_all(&mut self) -> Result<()> {
+        File::sync_all(self)
+    }
+    /// ref. [`std::io::File.sync_data()`](https://doc.rust-lang.org/std/fs/struct.File.html#method.sync_data)
+    #[inline]
+    fn sync_data(&mut self) -> Result<()> {
+        File::sync_data(self)
+    }
+    /// Call `sync_file_range()` on linux, and `sync_data()` on the others.
+    /// `sync_file_range()` writes out the dirty pages of the range and waits for them,
+    /// but it does not flush the file metadata nor the disk write cache.
+    fn sync_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+            // zero means up to the end of the file, and so does a length over off64_t.
+            let len = if len > libc::off64_t::MAX as u64 { 0 } else { len };
+            let flags = libc::SYNC_FILE_RANGE_WAIT_BEFORE
+                | libc::SYNC_FILE_RANGE_WRITE
+                | libc::SYNC_FILE_RANGE_WAIT_AFTER;
+            let r = unsafe {
+                libc::sync_file_range(
+                    self.as_raw_fd(),
+                    offset as libc::off64_t,
+                    len as libc::off64_t,
+                    flags,
+                )
+            };
+            if r != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (offset, len);
+            File::sync_data(self)
+        }
+    }
 }
 
-impl FileSync for BufFile {
+impl<T: Seek + Read + Write + FileSync> FileSync for RaBuf<T> {
     /// Flush buffer and call
     /// [`std::io::File.sync_all()`](https://doc.rust-lang.org/std/fs/struct.File.html#method.sync_all)
     #[inline]
     fn sync_all(&mut self) -> Result<()> {
         self.flush()?;
-        self.file.sync_all()
+        let end = self.end;
+        self.file
+            .sync_all()
+            .map_err(|err| RaBufError::new(&self.name, RaBufOp::Sync, 0, end, err).into())
     }
     /// Flush buffer and call
     /// [`std::io::File.sync_data()`](https://doc.rust-lang.org/std/fs/struct.File.html#method.sync_data)
     #[inline]
     fn sync_data(&mut self) -> Result<()> {
         self.flush()?;
-        self.file.sync_data()
+        let end = self.end;
+        self.file
+            .sync_data()
+            .map_err(|err| RaBufError::new(&self.name, RaBufOp::Sync, 0, end, err).into())
+    }
+    /// Flush buffer of the range and call `sync_range()` of the underlying file.
+    #[inline]
+    fn sync_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        let flush_len = if len == 0 { u64::MAX } else { len };
+        self.flush_range(offset, flush_len)?;
+        self.file
+            .sync_range(offset, len)
+            .map_err(|err| RaBufError::new(&self.name, RaBufOp::Sync, offset, len, err).into())
+    }
+}
+
+/// Copying a range of the underlying file in the kernel.
+pub trait FileCopyRange {
+    /// Returns the plain file for `copy_file_range()`.
+    /// The default is None, and the ranges are copied through the chunk cache.
+    fn as_plain_file(&self) -> Option<&File> {
+        None
+    }
+}
+
+impl FileCopyRange for File {
+    #[inline]
+    fn as_plain_file(&self) -> Option<&File> {
+        Some(self)
+    }
+}
+
+/// Deallocating a range of the underlying file.
+pub trait FilePunchHole {
+    /// Deallocates the range, which reads as zero after it, without changing the length.
+    /// Returns false if it is not supported. The default returns false.
+    fn punch_hole(&mut self, offset: u64, len: u64) -> Result<bool> {
+        let _ = (offset, len);
+        Ok(false)
+    }
+}
+
+impl FilePunchHole for File {
+    /// Call `fallocate()` with `FALLOC_FL_PUNCH_HOLE` on linux, and nothing todo on the others.
+    fn punch_hole(&mut self, offset: u64, len: u64) -> Result<bool> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+            if len == 0 {
+                return Ok(true);
+            }
+            let r = unsafe {
+                libc::fallocate(
+                    self.as_raw_fd(),
+                    libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+                    offset as libc::off_t,
+                    len as libc::off_t,
+                )
+            };
+            if r != 0 {
+                let err = std::io::Error::last_os_error();
+                return match err.raw_os_error() {
+                    Some(libc::EOPNOTSUPP) | Some(libc::ENOSYS) => Ok(false),
+                    _ => Err(err),
+                };
+            }
+            Ok(true)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (offset, len);
+            Ok(false)
+        }
+    }
+}
+
+/// Finding the data and the holes of the underlying sparse file.
+pub trait FileSeekData {
+    /// Returns the offset of the first data at or after `offset`,
+    /// or None if there is no data after it.
+    fn next_data(&mut self, offset: u64) -> Result<Option<u64>>;
+    /// Returns the offset of the first hole at or after `offset`,
+    /// or None if `offset` is at or after the end. The end is a hole.
+    fn next_hole(&mut self, offset: u64) -> Result<Option<u64>>;
+}
+
+impl FileSeekData for File {
+    /// Call `lseek()` with `SEEK_DATA` on linux.
+    /// On the others, the whole file is data.
+    fn next_data(&mut self, offset: u64) -> Result<Option<u64>> {
+        #[cfg(target_os = "linux")]
+        {
+            lseek_(self, offset, libc::SEEK_DATA)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let len = self.metadata()?.len();
+            Ok(if offset < len { Some(offset) } else { None })
+        }
+    }
+    /// Call `lseek()` with `SEEK_HOLE` on linux.
+    /// On the others, the end is the only hole.
+    fn next_hole(&mut self, offset: u64) -> Result<Option<u64>> {
+        #[cfg(target_os = "linux")]
+        {
+            lseek_(self, offset, libc::SEEK_HOLE)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let len = self.metadata()?.len();
+            Ok(if offset < len { Some(len) } else { None })
+        }
+    }
+}
+
+/// Call `lseek()` with `whence`, and returns None on `ENXIO`.
+#[cfg(target_os = "linux")]
+fn lseek_(file: &File, offset: u64, whence: std::os::raw::c_int) -> Result<Option<u64>> {
+    use std::os::unix::io::AsRawFd;
+    let r = unsafe { libc::lseek(file.as_raw_fd(), offset as libc::off_t, whence) };
+    if r < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::ENXIO) {
+            return Ok(None);
+        }
+        return Err(err);
+    }
+    Ok(Some(r as u64))
+}
+
+/// Copies the range between the plain files with `copy_file_range()` on linux,
+/// and returns the copied size. It stops at the end of `src`, and returns zero
+/// if the files do not support it. Nothing todo on the others.
+fn copy_file_range_(
+    name: &str,
+    src: &File,
+    src_off: u64,
+    dst: &File,
+    dst_off: u64,
+    len: u64,
+) -> Result<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+        let mut done = 0;
+        while done < len {
+            let mut off_in = (src_off + done) as i64;
+            let mut off_out = (dst_off + done) as i64;
+            let n = (len - done).min(1 << 30) as usize;
+            let r = unsafe {
+                libc::copy_file_range(
+                    src.as_raw_fd(),
+                    &mut off_in as *mut i64 as *mut _,
+                    dst.as_raw_fd(),
+                    &mut off_out as *mut i64 as *mut _,
+                    n,
+                    0,
+                )
+            };
+            if r < 0 {
+                let err = std::io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(libc::EINTR) => continue,
+                    Some(libc::ENOSYS)
+                    | Some(libc::EXDEV)
+                    | Some(libc::EINVAL)
+                    | Some(libc::EOPNOTSUPP) => break,
+                    _ => {
+                        return Err(RaBufError::new(
+                            name,
+                            RaBufOp::CopyRange,
+                            src_off + done,
+                            len - done,
+                            err,
+                        )
+                        .into())
+                    }
+                }
+            }
+            if r == 0 {
+                break;
+            }
+            done += r as u64;
+        }
+        Ok(done)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (name, src, src_off, dst, dst_off, len);
+        Ok(0)
     }
 }
 
@@ -216,7 +573,7 @@ pub trait SmallRead {
     fn read_exact_maybeslice(&mut self, size: usize) -> Result<MaybeSlice<'_>>;
 }
 
-impl SmallRead for BufFile {
+impl<T: Seek + Read> SmallRead for RaBuf<T> {
     /// Read one byte with a fast routine.
     #[inline]
     fn read_u8(&mut self) -> Result<u8> {
@@ -422,7 +779,7 @@ impl SmallRead for BufFile {
                 #[cfg(feature = "buf_debug")]
                 let data_len = self.chunks[idx].data.len();
                 #[cfg(not(feature = "buf_debug"))]
-                let data_len = unsafe { (*self.chunks.as_ptr().add(idx)).data.len() };
+                let data_len = unsafe { self.chunks.get_unchecked(idx).data.len() };
                 //
                 (idx, st, data_len - st)
             } else {
@@ -437,7 +794,7 @@ impl SmallRead for BufFile {
             #[cfg(not(feature = "buf_debug"))]
             let slice = unsafe {
                 std::slice::from_raw_parts(
-                    (*(self.chunks.as_ptr().add(idx))).data.as_ptr().add(st),
+                    self.chunks.get_unchecked(idx).data.as_ptr().add(st),
                     size,
                 )
             };
@@ -468,7 +825,7 @@ pub trait SmallWrite {
     fn write_zero(&mut self, size: u32) -> Result<()>;
 }
 
-impl SmallWrite for BufFile {
+impl<T: Seek + Read + Write> SmallWrite for RaBuf<T> {
     #[inline]
     fn write_u8(&mut self, val: u8) -> Result<()> {
         const SIZE: usize = 1;
@@ -487,6 +844,7 @@ impl SmallWrite for BufFile {
                 };
                 //
                 dest.copy_from_slice(&val.to_le_bytes());
+                self.mark_dirty_(curr);
                 self.pos += SIZE as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -518,6 +876,7 @@ impl SmallWrite for BufFile {
                 };
                 //
                 dest.copy_from_slice(&val.to_le_bytes());
+                self.mark_dirty_(curr);
                 self.pos += SIZE as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -549,6 +908,7 @@ impl SmallWrite for BufFile {
                 };
                 //
                 dest.copy_from_slice(&val.to_le_bytes());
+                self.mark_dirty_(curr);
                 self.pos += SIZE as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -580,6 +940,7 @@ impl SmallWrite for BufFile {
                 };
                 //
                 dest.copy_from_slice(&val.to_le_bytes());
+                self.mark_dirty_(curr);
                 self.pos += SIZE as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -616,6 +977,7 @@ impl SmallWrite for BufFile {
                     let val = unsafe { &*(val_slice.as_ptr().add(i)) };
                     dest.copy_from_slice(&val.to_le_bytes());
                 }
+                self.mark_dirty_(curr);
                 self.pos += size as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -679,6 +1041,7 @@ impl SmallWrite for BufFile {
                     let val = unsafe { &*val_slice2.as_ptr().add(i) };
                     dest.copy_from_slice(&val.to_le_bytes());
                 }
+                self.mark_dirty_(curr);
                 self.pos += size as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -743,6 +1106,7 @@ impl SmallWrite for BufFile {
                 //
                 dest.copy_from_slice(buf);
                 //
+                self.mark_dirty_(curr);
                 self.pos += buf_len as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -774,6 +1138,7 @@ impl SmallWrite for BufFile {
                     *item = 0u8;
                 }
                 //
+                self.mark_dirty_(curr);
                 self.pos += size as u64;
                 if self.end < self.pos {
                     self.end = self.pos;
@@ -828,7 +1193,7 @@ const DEFAULT_PER_MILLE: u16 = 20;
 #[derive(Debug)]
 struct Chunk {
     /// chunk data. it is a buffer for reading or writing.
-    pub data: Vec<u8>,
+    pub data: AlignedBuf,
     /// chunk offset. it is a offset from start of the file.
     offset: u64,
     /// dirty flag. we should write the chunk to the file.
@@ -839,25 +1204,35 @@ struct Chunk {
 }
 
 impl Chunk {
+    /// Makes a chunk at `offset` with the given buffer.
+    /// The buffer may be a recycled one, so the bytes that are not read from
+    /// the file are cleared to zero.
     fn new<U: Seek + Read>(
+        name: &str,
         offset: u64,
         end_pos: u64,
-        chunk_size: usize,
+        short_ok: bool,
+        mut data: AlignedBuf,
         file: &mut U,
     ) -> Result<Chunk> {
+        let chunk_size = data.len();
         if offset >= end_pos {
+            data.fill(0u8);
             return Ok(Chunk {
-                data: vec![0u8; chunk_size],
+                data,
                 offset,
                 dirty: false,
                 #[cfg(not(feature = "buf_overf_rem_all"))]
                 uses: 0,
             });
         }
-        file.seek(SeekFrom::Start(offset))?;
-        let mut data = vec![0u8; chunk_size];
+        let load_err = |err| RaBufError::new(name, RaBufOp::Load, offset, chunk_size as u64, err);
+        file.seek(SeekFrom::Start(offset)).map_err(load_err)?;
         if offset != end_pos {
             let end_off = (end_pos - offset) as usize;
+            if chunk_size > end_off {
+                data[end_off..].fill(0u8);
+            }
             #[cfg(feature = "buf_debug")]
             let buf = if chunk_size <= end_off {
                 &mut data[0..]
@@ -875,11 +1250,7 @@ impl Chunk {
                     },
                 )
             };
-            //
-            if let Err(err) = file.read_exact(buf) {
-                let _ = std::marker::PhantomData::<i32>;
-                return Err(err);
-            }
+            read_load(file, buf, short_ok).map_err(load_err)?;
         }
         Ok(Chunk {
             data,
@@ -891,17 +1262,20 @@ impl Chunk {
     }
     //
     #[cfg(not(feature = "buf_overf_rem"))]
-    fn read_inplace<U: Seek + Read + Write>(
+    fn read_inplace<U: Seek + Read>(
         &mut self,
+        name: &str,
         offset: u64,
         end_pos: u64,
+        short_ok: bool,
         file: &mut U,
     ) -> Result<()> {
         let chunk_size = self.data.len();
+        let load_err = |err| RaBufError::new(name, RaBufOp::Load, offset, chunk_size as u64, err);
         //
-        file.seek(SeekFrom::Start(offset))?;
+        file.seek(SeekFrom::Start(offset)).map_err(load_err)?;
         self.data.fill(0u8);
-        if offset != end_pos {
+        if offset < end_pos {
             let end_off = (end_pos - offset) as usize;
             #[cfg(feature = "buf_debug")]
             let buf = if chunk_size <= end_off {
@@ -920,11 +1294,7 @@ impl Chunk {
                     },
                 )
             };
-            //
-            if let Err(err) = file.read_exact(buf) {
-                let _ = std::marker::PhantomData::<i32>;
-                return Err(err);
-            }
+            read_load(file, buf, short_ok).map_err(load_err)?;
         }
         //
         self.dirty = false;
@@ -934,16 +1304,25 @@ impl Chunk {
         Ok(())
     }
     //
-    fn write<U: Seek + Read + Write>(&mut self, end_pos: u64, file: &mut U) -> Result<()> {
+    fn write<U: Seek>(
+        &mut self,
+        name: &str,
+        end_pos: u64,
+        file: &mut U,
+        write_fn: WriteFn<U>,
+    ) -> Result<()> {
         if !self.dirty {
             return Ok(());
         }
         if self.offset > end_pos {
             return Ok(());
         }
-        file.seek(SeekFrom::Start(self.offset))?;
         let end_off = (end_pos - self.offset) as usize;
         let chunk_size = self.data.len();
+        let offset = self.offset;
+        let len = chunk_size.min(end_off) as u64;
+        let write_err = |err| RaBufError::new(name, RaBufOp::WriteBack, offset, len, err);
+        file.seek(SeekFrom::Start(offset)).map_err(write_err)?;
         #[cfg(feature = "buf_debug")]
         let buf = if chunk_size <= end_off {
             &self.data[0..]
@@ -962,16 +1341,9 @@ impl Chunk {
             )
         };
         //
-        match file.write_all(buf) {
-            Ok(()) => {
-                self.dirty = false;
-                Ok(())
-            }
-            Err(err) => {
-                let _ = std::marker::PhantomData::<i32>;
-                Err(err)
-            }
-        }
+        write_fn(file, buf).map_err(write_err)?;
+        self.dirty = false;
+        Ok(())
     }
 }
 
@@ -1107,8 +1479,12 @@ impl OffsetIndex {
 }
 
 /// Generic random access buffer.
+///
+/// The dirty chunks are written on drop, but the error of it can not be
+/// returned. Call `flush()` before drop to see the errors. A failed flush
+/// on drop is printed to stderr.
 #[derive(Debug)]
-pub struct RaBuf<T: Seek + Read + Write> {
+pub struct RaBuf<T: Seek + Read> {
     /// The name of rabuf for debugging.
     name: String,
     /// The maximum number of chunk
@@ -1151,6 +1527,114 @@ pub struct RaBuf<T: Seek + Read + Write> {
     /// a count of miss.
     #[cfg(feature = "buf_print_hits")]
     count_of_miss: u64,
+    /// Recycled chunk buffers, reused instead of allocating new ones.
+    free_bufs: Vec<AlignedBuf>,
+    /// The pieces of `read_slices()` evicted while loading the range.
+    slices_buf: Vec<u8>,
+    /// The alignment of the start address of chunk buffers.
+    buf_align: usize,
+    /// The size in bytes from which reads and writes bypass the chunk cache.
+    /// zero is disabled.
+    bypass_threshold: usize,
+    /// Pinned chunk offsets and their pin counts, sorted by the offset.
+    pins: Vec<(u64, u32)>,
+    /// Access pattern hints as `(start, end, advice)`, sorted by the start.
+    advices: Vec<(u64, u64, Advice)>,
+    /// Set when the dirty data could not be written back to the file.
+    poisoned: bool,
+    /// The offset of the highest dirty chunk, zero if none,
+    /// or None if it is computed again on the next use.
+    dirty_top: Option<u64>,
+    /// `Write::write_all()` of the file, or None if it is read-only.
+    write_fn: Option<WriteFn<T>>,
+    /// `FileSeekData::next_data()` of the file in the sparse mode, or None.
+    next_data_fn: Option<NextDataFn<T>>,
+    /// a count of newly allocated chunk buffers.
+    #[cfg(feature = "buf_stats")]
+    stats_chunk_allocs: u64,
+    /// a count of reused chunk buffers.
+    #[cfg(feature = "buf_stats")]
+    stats_chunk_reuses: u64,
+    /// a count of chunks made zero without reading in the sparse mode.
+    #[cfg(feature = "buf_stats")]
+    stats_hole_chunks: u64,
+}
+
+/// `FileSeekData::next_data()` of the file.
+type NextDataFn<T> = fn(&mut T, u64) -> Result<Option<u64>>;
+
+/// `Write::write_all()` of the file.
+type WriteFn<T> = fn(&mut T, &[u8]) -> Result<()>;
+
+/// Returns the end of the range, or an error if it overflows.
+fn range_end(offset: u64, len: u64) -> Result<u64> {
+    offset.checked_add(len).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("range overflows: {} + {}", offset, len),
+        )
+    })
+}
+
+/// Returns the new position of a seek, without extending the end.
+pub(crate) fn seek_pos(curr: u64, end: u64, pos: SeekFrom) -> Result<u64> {
+    let new_pos = match pos {
+        SeekFrom::Start(x) => Some(x),
+        SeekFrom::End(x) => {
+            if x < 0 {
+                end.checked_sub((-x) as u64)
+            } else {
+                end.checked_add(x as u64)
+            }
+        }
+        SeekFrom::Current(x) => {
+            if x < 0 {
+                curr.checked_sub((-x) as u64)
+            } else {
+                curr.checked_add(x as u64)
+            }
+        }
+    };
+    new_pos.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        )
+    })
+}
+
+/// Reads until `buf` is full or the end of the file, and returns the read size.
+pub(crate) fn read_full<U: Read + ?Sized>(file: &mut U, buf: &mut [u8]) -> Result<usize> {
+    let mut done = 0;
+    while done < buf.len() {
+        match file.read(&mut buf[done..]) {
+            Ok(0) => break,
+            Ok(n) => done += n,
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(done)
+}
+
+/// Reads `buf` of a chunk load fully. A short read is `UnexpectedEof`, or
+/// the rest is zero if `short_ok`.
+pub(crate) fn read_load<U: Read + ?Sized>(
+    file: &mut U,
+    buf: &mut [u8],
+    short_ok: bool,
+) -> Result<()> {
+    let n = read_full(file, buf)?;
+    if n < buf.len() {
+        if !short_ok {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ));
+        }
+        buf[n..].fill(0u8);
+    }
+    Ok(())
 }
 
 // ref.) http://graphics.stanford.edu/~seander/bithacks.html#RoundUpPowerOf2
@@ -1173,135 +1657,257 @@ impl<T: Seek + Read + Write> RaBuf<T> {
     /// Creates a new BufFile.
     /// number of chunk: 16, chunk size: 4096
     pub fn new(name: &str, file: T) -> Result<RaBuf<T>> {
-        #[cfg(not(feature = "buf_auto_buf_size"))]
-        {
-            Self::with_capacity(name, file, CHUNK_SIZE, DEFAULT_NUM_CHUNKS)
-        }
-        #[cfg(feature = "buf_auto_buf_size")]
-        {
-            Self::with_per_mille(name, file, CHUNK_SIZE, DEFAULT_PER_MILLE)
-        }
+        Self::new_(name, file, Some(T::write_all))
     }
     /// Creates a new BufFile with the specified number of chunks.
     /// chunk_size is MUST power of 2.
     pub fn with_capacity(
         name: &str,
-        mut file: T,
+        file: T,
         chunk_size: u32,
         max_num_chunks: u16,
     ) -> Result<RaBuf<T>> {
-        debug_assert!(chunk_size == roundup_powerof2(chunk_size));
-        debug_assert!(max_num_chunks > 0);
-        let max_num_chunks = max_num_chunks as usize;
-        let chunk_mask = !(chunk_size as u64 - 1);
-        let chunk_size = chunk_size as usize;
-        let end = file.seek(SeekFrom::End(0))?;
-        file.rewind()?;
-        //
-        Ok(Self {
-            name: name.to_string(),
-            max_num_chunks,
-            chunk_size,
-            chunk_mask,
-            chunks: Vec::with_capacity(max_num_chunks),
-            map: OffsetIndex::with_capacity(max_num_chunks),
-            file,
-            pos: 0,
-            end,
-            fetch_cache: None,
-            #[cfg(feature = "buf_lru")]
-            uses_cnt: 0,
-            #[cfg(feature = "buf_stats")]
-            stats_min_uses: 0,
-            #[cfg(feature = "buf_stats")]
-            stats_max_uses: 0,
-            #[cfg(feature = "buf_auto_buf_size")]
-            auto_buf_size: None,
-            #[cfg(feature = "buf_print_hits")]
-            count_of_hits_fc: 0,
-            #[cfg(feature = "buf_print_hits")]
-            count_of_hits: 0,
-            #[cfg(feature = "buf_print_hits")]
-            count_of_miss: 0,
-        })
+        Self::with_capacity_(name, file, chunk_size, max_num_chunks, Some(T::write_all))
     }
     /// Create a new BufFile with auto buffer size per mille of file size.
     /// chunk_size is MUST power of 2.
     #[cfg(feature = "buf_auto_buf_size")]
     pub fn with_per_mille(
         name: &str,
-        mut file: T,
+        file: T,
         chunk_size: u32,
         per_mille: u16,
     ) -> Result<RaBuf<T>> {
-        debug_assert!(chunk_size == roundup_powerof2(chunk_size));
-        let chunk_mask = !(chunk_size as u64 - 1);
-        let chunk_size = chunk_size as usize;
-        let auto_buf_size = AutoBufferSize::with_per_mille(per_mille);
-        let end = file.seek(SeekFrom::End(0))?;
-        let max_num_chunks = (auto_buf_size.buffer_size(end)? / chunk_size) + 1;
-        file.rewind()?;
-        //
-        Ok(Self {
-            name: name.to_string(),
-            max_num_chunks,
-            chunk_size,
-            chunk_mask,
-            chunks: Vec::with_capacity(max_num_chunks),
-            map: OffsetIndex::with_capacity(max_num_chunks),
-            file,
-            pos: 0,
-            end,
-            fetch_cache: None,
-            #[cfg(feature = "buf_lru")]
-            uses_cnt: 0,
-            #[cfg(feature = "buf_stats")]
-            stats_min_uses: 0,
-            #[cfg(feature = "buf_stats")]
-            stats_max_uses: 0,
-            auto_buf_size: Some(auto_buf_size),
-            #[cfg(feature = "buf_print_hits")]
-            count_of_hits_fc: 0,
-            #[cfg(feature = "buf_print_hits")]
-            count_of_hits: 0,
-            #[cfg(feature = "buf_print_hits")]
-            count_of_miss: 0,
-        })
+        Self::with_per_mille_(name, file, chunk_size, per_mille, Some(T::write_all))
     }
-    /// Flush and clear all buffer chunks.
-    #[inline]
-    pub fn clear(&mut self) -> Result<()> {
-        self.flush()?;
-        self.fetch_cache = None;
-        #[cfg(not(feature = "buf_pin_zero"))]
-        {
-            self.chunks.clear();
-            self.map.clear();
+    /// Writes the dirty chunks that overlap the range.
+    pub fn flush_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        for idx in self.overlapping_chunks(offset, len) {
+            self.write_back_(idx)?;
         }
-        #[cfg(feature = "buf_pin_zero")]
-        {
-            if let Some(idx) = self.map.get(&0) {
-                let chunk_zero = self.chunks.remove(idx);
-                self.chunks.clear();
-                self.map.clear();
-                self.chunks.push(chunk_zero);
-                self.map.insert(&0, 0);
+        Ok(())
+    }
+    /// Drops the cached chunks that overlap the range, so that the next
+    /// access reads them again from the underlying file.
+    ///
+    /// The dirty data is written before dropping, or discarded if `discard_dirty`
+    /// is true. The pinned chunks are read again in place.
+    pub fn invalidate_range(&mut self, offset: u64, len: u64, discard_dirty: bool) -> Result<()> {
+        let mut discarded = false;
+        let mut pinned = Vec::new();
+        let mut vec = self.overlapping_chunks(offset, len);
+        // removes from the tail so that the indices stay valid.
+        vec.sort_unstable();
+        while let Some(idx) = vec.pop() {
+            if discard_dirty {
+                discarded |= self.chunks[idx].dirty;
             } else {
-                self.chunks.clear();
-                self.map.clear();
+                self.write_back_(idx)?;
+            }
+            if self.is_pinned(self.chunks[idx].offset) {
+                pinned.push(self.chunks[idx].offset);
             }
+            self.remove_chunk_(idx);
         }
-        #[cfg(feature = "buf_lru")]
-        {
-            // clear LRU(: Least Reacently Used) counter
-            self.uses_cnt = 0;
+        if discarded {
+            // the discarded data may have extended the file.
+            let mut end = self.file.seek(SeekFrom::End(0))?;
+            for chunk in self.chunks.iter().filter(|a| a.dirty) {
+                let chunk_end = chunk.offset + chunk.data.len() as u64;
+                end = end.max(chunk_end.min(self.end));
+            }
+            self.end = end;
+        }
+        for off in pinned {
+            let _ = self.add_chunk(off)?;
         }
         Ok(())
     }
-    /// Name for debugging
-    #[inline]
-    pub fn name(&self) -> String {
-        self.name.clone()
+    /// Takes out the dirty chunks as `(offset, bytes)`, sorted by the offset,
+    /// and drops all of the cached chunks. The bytes are clamped to the end.
+    ///
+    /// The poisoned state is cleared, and the end is set back to the length
+    /// of the underlying file.
+    pub fn take_unwritten(&mut self) -> Result<Vec<(u64, Vec<u8>)>> {
+        let mut vec = Vec::new();
+        for chunk in self.chunks.iter().filter(|a| a.dirty) {
+            let len = self.end.saturating_sub(chunk.offset);
+            let len = len.min(chunk.data.len() as u64) as usize;
+            vec.push((chunk.offset, chunk.data[..len].to_vec()));
+        }
+        vec.sort_by_key(|a| a.0);
+        self.poisoned = false;
+        self.invalidate_range(0, u64::MAX, true)?;
+        if self.end < self.pos {
+            self.pos = self.end;
+        }
+        Ok(vec)
+    }
+    /// Writes a value at the current position.
+    ///
+    /// A value with `FIXED_SIZE` that fits in the current chunk is encoded
+    /// directly into the chunk.
+    #[inline]
+    pub fn write_value<V: RaEncode + ?Sized>(&mut self, val: &V) -> Result<()> {
+        if let Some(size) = V::FIXED_SIZE {
+            if size <= self.chunk_size {
+                let curr = self.pos;
+                let chunk = self.fetch_chunk(curr)?;
+                let st = (curr - chunk.offset) as usize;
+                if st + size <= chunk.data.len() {
+                    chunk.dirty = true;
+                    //
+                    #[cfg(feature = "buf_debug")]
+                    let mut slice = &mut chunk.data[st..(st + size)];
+                    #[cfg(not(feature = "buf_debug"))]
+                    let mut slice = unsafe {
+                        std::slice::from_raw_parts_mut(chunk.data.as_mut_ptr().add(st), size)
+                    };
+                    //
+                    val.encode(&mut slice)?;
+                    self.mark_dirty_(curr);
+                    self.pos += size as u64;
+                    if self.end < self.pos {
+                        self.end = self.pos;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        val.encode(self)
+    }
+    /// Writes the length of `buf` in the encoding of `width`, followed by `buf`.
+    ///
+    /// A length over `width.max_len()` is an `InvalidInput` error.
+    pub fn write_bytes_prefixed(&mut self, buf: &[u8], width: LenWidth) -> Result<()> {
+        prefixed::write_len(self, buf.len() as u64, width)?;
+        if buf.len() <= self.chunk_size {
+            self.write_all_small(buf)
+        } else {
+            self.write_all(buf)
+        }
+    }
+    /// Writes the length of `s` in the encoding of `width`, followed by the UTF-8 bytes.
+    #[inline]
+    pub fn write_str_prefixed(&mut self, s: &str, width: LenWidth) -> Result<()> {
+        self.write_bytes_prefixed(s.as_bytes(), width)
+    }
+    /// Calls `f` with `len` bytes at `offset` to patch them in place,
+    /// and returns the result.
+    ///
+    /// The slice is in the chunk if the range lies in one chunk, or else
+    /// a temporary buffer that is written back after `f` returns.
+    /// The chunks are marked dirty, and the end is extended to the range.
+    /// The current position is not changed.
+    pub fn with_slice_mut<R, F>(&mut self, offset: u64, len: usize, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let ed = offset.checked_add(len as u64).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("slice range overflows: {} + {}", offset, len),
+            )
+        })?;
+        if len == 0 {
+            return Ok(f(&mut []));
+        }
+        let chunk = self.fetch_chunk(offset)?;
+        let st = (offset - chunk.offset) as usize;
+        let r = if st + len <= chunk.data.len() {
+            chunk.dirty = true;
+            f(&mut chunk.data[st..(st + len)])
+        } else {
+            let mut buf = vec![0u8; len];
+            self.read_at_(offset, &mut buf)?;
+            let r = f(&mut buf);
+            self.write_at_(offset, &buf)?;
+            r
+        };
+        self.mark_dirty_(offset);
+        if self.end < ed {
+            self.end = ed;
+        }
+        Ok(r)
+    }
+    /// Fills `len` bytes at `offset` with the repeated `pattern`.
+    ///
+    /// The chunks are filled in place one by one, without allocating a buffer.
+    /// The current position is not changed.
+    pub fn fill(&mut self, offset: u64, len: u64, pattern: &[u8]) -> Result<()> {
+        if pattern.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "fill pattern is empty",
+            ));
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        let ed = offset.checked_add(len).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("fill range overflows: {} + {}", offset, len),
+            )
+        })?;
+        let mut curr = offset;
+        while curr < ed {
+            let chunk = self.fetch_chunk(curr)?;
+            let st = (curr - chunk.offset) as usize;
+            let n = (chunk.data.len() - st).min((ed - curr) as usize);
+            chunk.dirty = true;
+            let dest = &mut chunk.data[st..(st + n)];
+            if pattern.len() == 1 {
+                dest.fill(pattern[0]);
+            } else {
+                let phase = ((curr - offset) % pattern.len() as u64) as usize;
+                for (d, s) in dest.iter_mut().zip(pattern.iter().cycle().skip(phase)) {
+                    *d = *s;
+                }
+            }
+            self.mark_dirty_(curr);
+            curr += n as u64;
+        }
+        if self.end < ed {
+            self.end = ed;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Seek + Read> RaBuf<T> {
+    /// Flush and clear all buffer chunks.
+    #[inline]
+    pub fn clear(&mut self) -> Result<()> {
+        self.flush_()?;
+        self.fetch_cache = None;
+        // keeps the pinned chunks, and the chunk zero with `buf_pin_zero`
+        let mut i = 0;
+        while i < self.chunks.len() {
+            let offset = self.chunks[i].offset;
+            if (cfg!(feature = "buf_pin_zero") && offset == 0) || self.is_pinned(offset) {
+                i += 1;
+            } else {
+                let chunk = self.chunks.swap_remove(i);
+                self.free_bufs.push(chunk.data);
+            }
+        }
+        self.dirty_top = None;
+        self.map.clear();
+        for (idx, chunk) in self.chunks.iter().enumerate() {
+            self.map.insert(&chunk.offset, idx);
+        }
+        #[cfg(feature = "buf_lru")]
+        {
+            // clear LRU(: Least Reacently Used) counter
+            self.uses_cnt = 0;
+        }
+        Ok(())
+    }
+    /// Name for debugging
+    #[inline]
+    pub fn name(&self) -> String {
+        self.name.clone()
     }
     /// make preparation
     #[inline]
@@ -1309,6 +1915,232 @@ impl<T: Seek + Read + Write> RaBuf<T> {
         let _ = self.fetch_chunk(offset)?;
         Ok(())
     }
+    /// Pins the chunks that cover the range, and loads them.
+    ///
+    /// The pinned chunks stay in the cache across `clear()` and the eviction
+    /// on overflow, and they count against the capacity of the cache.
+    /// The pins are counted, so each `pin_range()` should be paired with
+    /// an `unpin_range()` of the same range.
+    /// It is an error to pin chunks more than the cache can hold
+    /// with one chunk left for the others.
+    pub fn pin_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let ed = range_end(offset, len)?;
+        let chunk_size = self.chunk_size as u64;
+        let st = offset & self.chunk_mask;
+        let num = ((ed - 1) / chunk_size - offset / chunk_size + 1) as usize;
+        let num_pinned = self.pins_in_(st, ed).len();
+        let num_new = num - num_pinned;
+        let mut capacity = self.max_num_chunks.saturating_sub(1);
+        if cfg!(feature = "buf_pin_zero") && !self.is_pinned(0) && st != 0 {
+            // the chunk zero may be resident
+            capacity = capacity.saturating_sub(1);
+        }
+        if num_new > capacity.saturating_sub(self.pins.len()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "can not pin {} chunks more, pinned: {}, capacity: {}",
+                    num_new,
+                    self.pins.len(),
+                    capacity
+                ),
+            ));
+        }
+        let offsets = self.covering_chunks(offset, len)?;
+        for &off in offsets.iter() {
+            match self.pins.binary_search_by(|a| a.0.cmp(&off)) {
+                Ok(x) => self.pins[x].1 += 1,
+                Err(x) => self.pins.insert(x, (off, 1)),
+            }
+        }
+        for &off in offsets.iter() {
+            if let Err(err) = self.fetch_chunk(off) {
+                // the pins are not left behind.
+                self.unpin_range(offset, len);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+    /// Unpins the chunks that cover the range.
+    /// The chunks that are not pinned are ignored.
+    pub fn unpin_range(&mut self, offset: u64, len: u64) {
+        if len == 0 {
+            return;
+        }
+        let st = offset & self.chunk_mask;
+        let ed = offset.saturating_add(len);
+        let range = self.pins_in_(st, ed);
+        for pin in self.pins[range.clone()].iter_mut() {
+            pin.1 -= 1;
+        }
+        let mut idx = range.start;
+        for _ in range {
+            if self.pins[idx].1 == 0 {
+                self.pins.remove(idx);
+            } else {
+                idx += 1;
+            }
+        }
+    }
+    /// Returns true if the buffer is poisoned.
+    ///
+    /// When a dirty chunk can not be written back to the underlying file,
+    /// the buffer keeps the chunk and enters the poisoned state, in which
+    /// reads and writes return an error. It recovers when `flush()` writes
+    /// all of the dirty chunks, or when the unwritten chunks are taken out
+    /// with `take_unwritten()`. A failure in `drop()` can not be reported,
+    /// so call `flush()` before dropping the buffer.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+    /// Sets the size in bytes from which `read()` and `write()` bypass the chunk cache.
+    ///
+    /// A large transfer goes straight to the underlying file for the chunks
+    /// that it covers fully and that are not cached. The cached chunks in
+    /// the range are read from or written into the cache, and the partial
+    /// head and tail chunks go through the cache as usual.
+    /// zero is disabled, and it is the default.
+    #[inline]
+    pub fn set_bypass_threshold(&mut self, threshold: usize) {
+        self.bypass_threshold = threshold;
+    }
+    /// Reads a value at the current position.
+    ///
+    /// A value with `FIXED_SIZE` that fits in the current chunk is decoded
+    /// directly from the chunk.
+    #[inline]
+    pub fn read_value<V: RaDecode>(&mut self) -> Result<V> {
+        if let Some(size) = V::FIXED_SIZE {
+            if size <= self.chunk_size {
+                let curr = self.pos;
+                let chunk = self.fetch_chunk(curr)?;
+                let st = (curr - chunk.offset) as usize;
+                if st + size <= chunk.data.len() {
+                    #[cfg(feature = "buf_debug")]
+                    let mut slice = &chunk.data[st..(st + size)];
+                    #[cfg(not(feature = "buf_debug"))]
+                    let mut slice =
+                        unsafe { std::slice::from_raw_parts(chunk.data.as_ptr().add(st), size) };
+                    //
+                    let val = V::decode(&mut slice)?;
+                    self.pos += size as u64;
+                    return Ok(val);
+                }
+            }
+        }
+        V::decode(self)
+    }
+    /// Reads the bytes written by `write_bytes_prefixed()`.
+    ///
+    /// The bytes that lie in one chunk are returned as a slice of the chunk.
+    /// A length beyond the end is an `UnexpectedEof` error.
+    pub fn read_bytes_prefixed(&mut self, width: LenWidth) -> Result<MaybeSlice<'_>> {
+        let len = prefixed::read_len(self, width)?;
+        if self.pos > self.end || len > self.end - self.pos {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("length beyond the end: {}", len),
+            ));
+        }
+        self.read_exact_maybeslice(len as usize)
+    }
+    /// Reads the string written by `write_str_prefixed()`.
+    ///
+    /// The string that lies in one chunk is borrowed from the chunk.
+    /// Invalid UTF-8 is an `InvalidData` error.
+    pub fn read_str_prefixed(&mut self, width: LenWidth) -> Result<Cow<'_, str>> {
+        let invalid = |e: std::str::Utf8Error| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+        };
+        match self.read_bytes_prefixed(width)? {
+            MaybeSlice::Slice(x) => Ok(Cow::Borrowed(std::str::from_utf8(x).map_err(invalid)?)),
+            MaybeSlice::Buffer(v) => match String::from_utf8(v) {
+                Ok(s) => Ok(Cow::Owned(s)),
+                Err(e) => Err(invalid(e.utf8_error())),
+            },
+        }
+    }
+    /// Returns `len` bytes at `offset` as the slices of the chunks, without
+    /// copying them into a contiguous buffer.
+    ///
+    /// A range over more chunks than the cache can hold is read in place of
+    /// the evicted chunks into a buffer that is reused.
+    /// The current position is not changed.
+    pub fn read_slices(&mut self, offset: u64, len: u64) -> Result<MaybeSlices<'_>> {
+        if offset > self.end || len > self.end - offset {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        let offsets = self.covering_chunks(offset, len)?;
+        for &off in offsets.iter() {
+            let _ = self.fetch_chunk(off)?;
+        }
+        // the chunks evicted by the later ones
+        let chunk_size = self.chunk_size as u64;
+        let evicted: Vec<u64> = offsets
+            .iter()
+            .copied()
+            .filter(|off| self.map.get(off).is_none())
+            .collect();
+        let mut buf = std::mem::take(&mut self.slices_buf);
+        buf.clear();
+        buf.resize(evicted.len() * self.chunk_size, 0u8);
+        for (i, &off) in evicted.iter().enumerate() {
+            // the rest after the end is zero
+            let n = (self.end - off).min(chunk_size) as usize;
+            let dest = &mut buf[(i * self.chunk_size)..(i * self.chunk_size + n)];
+            let short_ok = self.short_load_ok_(off);
+            let load_err = |err| RaBufError::new(&self.name, RaBufOp::Load, off, chunk_size, err);
+            self.file.seek(SeekFrom::Start(off)).map_err(load_err)?;
+            read_load(&mut self.file, dest, short_ok).map_err(load_err)?;
+        }
+        self.slices_buf = buf;
+        //
+        let ed = offset + len;
+        let mut pieces = Vec::with_capacity(offsets.len());
+        let mut evicted_idx = 0;
+        for &off in offsets.iter() {
+            let data: &[u8] = match self.map.get(&off) {
+                Some(idx) => &self.chunks[idx].data,
+                None => {
+                    let st = evicted_idx * self.chunk_size;
+                    evicted_idx += 1;
+                    &self.slices_buf[st..(st + self.chunk_size)]
+                }
+            };
+            let st = offset.max(off) - off;
+            let n = ed.min(off + chunk_size) - off;
+            pieces.push(&data[(st as usize)..(n as usize)]);
+        }
+        Ok(MaybeSlices::new(pieces))
+    }
+    /// Calls `f` with `len` bytes at `offset`, and returns the result.
+    ///
+    /// The slice is in the chunk if the range lies in one chunk, or else
+    /// a temporary buffer. The current position is not changed.
+    pub fn with_slice<R, F>(&mut self, offset: u64, len: usize, f: F) -> Result<R>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        if offset > self.end || len as u64 > self.end - offset {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        if len == 0 {
+            return Ok(f(&[]));
+        }
+        let chunk = self.fetch_chunk(offset)?;
+        let st = (offset - chunk.offset) as usize;
+        if st + len <= chunk.data.len() {
+            return Ok(f(&chunk.data[st..(st + len)]));
+        }
+        let mut buf = vec![0u8; len];
+        self.read_at_(offset, &mut buf)?;
+        Ok(f(&buf))
+    }
     ///
     #[cfg(feature = "buf_stats")]
     pub fn buf_stats(&self) -> Vec<(String, i64)> {
@@ -1321,11 +2153,371 @@ impl<T: Seek + Read + Write> RaBuf<T> {
             "BufFile.stats_max_uses".to_string(),
             self.stats_max_uses as i64,
         ));
+        vec.push((
+            "BufFile.stats_chunk_allocs".to_string(),
+            self.stats_chunk_allocs as i64,
+        ));
+        vec.push((
+            "BufFile.stats_chunk_reuses".to_string(),
+            self.stats_chunk_reuses as i64,
+        ));
+        vec.push((
+            "BufFile.stats_hole_chunks".to_string(),
+            self.stats_hole_chunks as i64,
+        ));
         vec
     }
 }
 
-impl<T: Seek + Read + Write> RaBuf<T> {
+impl<T: Seek + Read + Write + FileCopyRange> RaBuf<T> {
+    /// Copies `len` bytes from `src_off` to `dst_off` in this buffer.
+    ///
+    /// The ranges may overlap, like `memmove()`. The bytes are copied
+    /// through the chunk cache a chunk at a time, or with `copy_file_range()`
+    /// when the underlying file is a plain file, the ranges do not overlap,
+    /// and no chunk of them is cached. The current position is not changed.
+    /// It is an error if the destination range overflows.
+    pub fn copy_within(&mut self, src_off: u64, dst_off: u64, len: u64) -> Result<()> {
+        self.check_copy_src_(src_off, len)?;
+        let _ = range_end(dst_off, len)?;
+        if len == 0 || src_off == dst_off {
+            return Ok(());
+        }
+        let overlap = src_off < dst_off + len && dst_off < src_off + len;
+        let mut done = 0;
+        if !overlap
+            && self.overlapping_chunks(src_off, len).is_empty()
+            && self.overlapping_chunks(dst_off, len).is_empty()
+        {
+            if let Some(file) = self.file.as_plain_file() {
+                done = copy_file_range_(&self.name, file, src_off, file, dst_off, len)?;
+                self.end = self.end.max(dst_off + done);
+            }
+        }
+        if done == len {
+            return Ok(());
+        }
+        let (src_off, dst_off, len) = (src_off + done, dst_off + done, len - done);
+        let saved_pos = self.pos;
+        let mut buf = vec![0u8; (len as usize).min(self.chunk_size)];
+        let backward = overlap && src_off < dst_off;
+        let mut rest = len;
+        while rest > 0 {
+            // a piece in a source chunk, from the tail if backward.
+            let (st, n) = if backward {
+                let ed = src_off + rest;
+                let st = ((ed - 1) & self.chunk_mask).max(src_off);
+                (st, ed - st)
+            } else {
+                let st = src_off + len - rest;
+                let chunk_ed = (st & self.chunk_mask) + self.chunk_size as u64;
+                (st, (chunk_ed - st).min(rest))
+            };
+            let piece = &mut buf[..n as usize];
+            self.pos = st;
+            self.read_exact(piece)?;
+            self.pos = dst_off + (st - src_off);
+            self.write_all(piece)?;
+            rest -= n;
+        }
+        self.pos = saved_pos;
+        Ok(())
+    }
+    /// Copies `len` bytes from `src_off` of this buffer to `dst_off` of `dst`.
+    ///
+    /// The bytes are copied from the chunks of this buffer into `dst`
+    /// a chunk at a time, or with `copy_file_range()` when the both underlying
+    /// files are plain files, and no chunk of the ranges is cached.
+    /// The current positions are not changed.
+    /// It is an error if the destination range overflows.
+    pub fn copy_range_to<U: Seek + Read + Write + FileCopyRange>(
+        &mut self,
+        dst: &mut RaBuf<U>,
+        src_off: u64,
+        dst_off: u64,
+        len: u64,
+    ) -> Result<()> {
+        self.check_copy_src_(src_off, len)?;
+        let _ = range_end(dst_off, len)?;
+        dst.check_poisoned_()?;
+        let mut done = 0;
+        if len > 0
+            && self.overlapping_chunks(src_off, len).is_empty()
+            && dst.overlapping_chunks(dst_off, len).is_empty()
+        {
+            if let (Some(src_file), Some(dst_file)) =
+                (self.file.as_plain_file(), dst.file.as_plain_file())
+            {
+                done = copy_file_range_(&self.name, src_file, src_off, dst_file, dst_off, len)?;
+                dst.end = dst.end.max(dst_off + done);
+            }
+        }
+        let saved_pos = dst.pos;
+        while done < len {
+            let offset = src_off + done;
+            let chunk = self.fetch_chunk(offset)?;
+            let st = (offset - chunk.offset) as usize;
+            let n = (chunk.data.len() - st).min((len - done) as usize);
+            dst.pos = dst_off + done;
+            dst.write_all(&chunk.data[st..(st + n)])?;
+            done += n as u64;
+        }
+        dst.pos = saved_pos;
+        Ok(())
+    }
+    /// Inserts `buf` at `offset`, shifting the bytes after it to the tail.
+    ///
+    /// The bytes are shifted through the chunk cache from the tail, a chunk
+    /// at a time. `offset` MUST be less than or equal to the end.
+    /// The current position is not changed.
+    pub fn insert_bytes(&mut self, offset: u64, buf: &[u8]) -> Result<()> {
+        self.check_copy_src_(offset, 0)?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let tail = self.end - offset;
+        self.copy_within(offset, offset + buf.len() as u64, tail)?;
+        let saved_pos = self.pos;
+        self.pos = offset;
+        self.write_all(buf)?;
+        self.pos = saved_pos;
+        Ok(())
+    }
+    /// Returns an error if the source range is over the end.
+    fn check_copy_src_(&self, src_off: u64, len: u64) -> Result<()> {
+        self.check_poisoned_()?;
+        match src_off.checked_add(len) {
+            Some(ed) if ed <= self.end => Ok(()),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "rabuf \"{}\": copy source is over the end: {} + {} > {}",
+                    self.name, src_off, len, self.end
+                ),
+            )),
+        }
+    }
+}
+
+impl<T: Seek + Read + Write + FileSeekData> RaBuf<T> {
+    /// Sets the sparse mode.
+    ///
+    /// In the sparse mode, a chunk is loaded after asking the file with
+    /// `next_data()`, and a chunk that has no data is made zero without reading.
+    pub fn set_sparse(&mut self, sparse: bool) {
+        self.next_data_fn = if sparse { Some(T::next_data) } else { None };
+    }
+    /// Returns the offset of the first data at or after `offset`,
+    /// or None if there is no data before the end.
+    ///
+    /// The dirty chunks are written before asking the file.
+    pub fn next_data(&mut self, offset: u64) -> Result<Option<u64>> {
+        if offset >= self.end {
+            return Ok(None);
+        }
+        self.flush()?;
+        let end = self.end;
+        match self.file.next_data(offset)? {
+            Some(x) if x < end => Ok(Some(x)),
+            _ => Ok(None),
+        }
+    }
+    /// Returns the offset of the first hole at or after `offset`,
+    /// or None if `offset` is at or after the end. The end is a hole.
+    ///
+    /// The dirty chunks are written before asking the file.
+    pub fn next_hole(&mut self, offset: u64) -> Result<Option<u64>> {
+        if offset >= self.end {
+            return Ok(None);
+        }
+        self.flush()?;
+        let end = self.end;
+        let hole = self.file.next_hole(offset)?.unwrap_or(end);
+        Ok(Some(hole.min(end)))
+    }
+}
+
+impl<T: Seek + Read + Write + FilePunchHole> RaBuf<T> {
+    /// Sets `len` bytes at `offset` to zero.
+    ///
+    /// The chunks that the range fully covers before the end are deallocated
+    /// with `punch_hole()` of the underlying file, and their cached copies
+    /// are cleared to zero. The rest of the range, or all of it if punching
+    /// is not supported, is filled with zero through the chunk cache.
+    /// The current position is not changed.
+    pub fn zero_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        self.check_poisoned_()?;
+        let chunk_size = self.chunk_size as u64;
+        let ed = offset.saturating_add(len);
+        let punch_st = offset.saturating_add(chunk_size - 1) & self.chunk_mask;
+        let punch_ed = ed.min(self.end) & self.chunk_mask;
+        if punch_st >= punch_ed {
+            return self.fill(offset, len, &[0u8]);
+        }
+        let punch_len = punch_ed - punch_st;
+        let punched = self.file.punch_hole(punch_st, punch_len).map_err(|err| {
+            RaBufError::new(&self.name, RaBufOp::PunchHole, punch_st, punch_len, err)
+        })?;
+        if punched {
+            // the dirty copies stay dirty, so that they are written as zero.
+            for idx in self.overlapping_chunks(punch_st, punch_len) {
+                self.chunks[idx].data.fill(0u8);
+            }
+        } else {
+            self.fill(punch_st, punch_len, &[0u8])?;
+        }
+        self.fill(offset, punch_st - offset, &[0u8])?;
+        self.fill(punch_ed, ed - punch_ed, &[0u8])
+    }
+}
+
+impl<T: Seek + Read + Write + FileCopyRange + FileSetLen> RaBuf<T> {
+    /// Removes `len` bytes at `offset`, shifting the bytes after it to the head,
+    /// and truncates the file.
+    ///
+    /// The bytes are shifted through the chunk cache from the head, a chunk
+    /// at a time. The current position is not changed, but it is clamped to
+    /// the new end.
+    pub fn remove_bytes(&mut self, offset: u64, len: u64) -> Result<()> {
+        self.check_copy_src_(offset, len)?;
+        if len == 0 {
+            return Ok(());
+        }
+        let tail = self.end - offset - len;
+        self.copy_within(offset + len, offset, tail)?;
+        let end = self.end - len;
+        self.set_len(end)
+    }
+}
+
+impl<T: Seek + Read> RaBuf<T> {
+    /// Creates a new RaBuf that writes with `write_fn`, or a read-only one with None.
+    fn new_(name: &str, file: T, write_fn: Option<WriteFn<T>>) -> Result<RaBuf<T>> {
+        #[cfg(not(feature = "buf_auto_buf_size"))]
+        {
+            Self::with_capacity_(name, file, CHUNK_SIZE, DEFAULT_NUM_CHUNKS, write_fn)
+        }
+        #[cfg(feature = "buf_auto_buf_size")]
+        {
+            Self::with_per_mille_(name, file, CHUNK_SIZE, DEFAULT_PER_MILLE, write_fn)
+        }
+    }
+    fn with_capacity_(
+        name: &str,
+        mut file: T,
+        chunk_size: u32,
+        max_num_chunks: u16,
+        write_fn: Option<WriteFn<T>>,
+    ) -> Result<RaBuf<T>> {
+        debug_assert!(chunk_size == roundup_powerof2(chunk_size));
+        debug_assert!(max_num_chunks > 0);
+        let max_num_chunks = max_num_chunks as usize;
+        let chunk_mask = !(chunk_size as u64 - 1);
+        let chunk_size = chunk_size as usize;
+        let end = file.seek(SeekFrom::End(0))?;
+        file.rewind()?;
+        //
+        Ok(Self {
+            name: name.to_string(),
+            max_num_chunks,
+            chunk_size,
+            chunk_mask,
+            chunks: Vec::with_capacity(max_num_chunks),
+            map: OffsetIndex::with_capacity(max_num_chunks),
+            file,
+            pos: 0,
+            end,
+            fetch_cache: None,
+            #[cfg(feature = "buf_lru")]
+            uses_cnt: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_min_uses: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_max_uses: 0,
+            #[cfg(feature = "buf_auto_buf_size")]
+            auto_buf_size: None,
+            #[cfg(feature = "buf_print_hits")]
+            count_of_hits_fc: 0,
+            #[cfg(feature = "buf_print_hits")]
+            count_of_hits: 0,
+            #[cfg(feature = "buf_print_hits")]
+            count_of_miss: 0,
+            free_bufs: Vec::new(),
+            slices_buf: Vec::new(),
+            buf_align: 1,
+            bypass_threshold: 0,
+            pins: Vec::new(),
+            advices: Vec::new(),
+            poisoned: false,
+            dirty_top: Some(0),
+            write_fn,
+            next_data_fn: None,
+            #[cfg(feature = "buf_stats")]
+            stats_chunk_allocs: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_chunk_reuses: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_hole_chunks: 0,
+        })
+    }
+    #[cfg(feature = "buf_auto_buf_size")]
+    fn with_per_mille_(
+        name: &str,
+        mut file: T,
+        chunk_size: u32,
+        per_mille: u16,
+        write_fn: Option<WriteFn<T>>,
+    ) -> Result<RaBuf<T>> {
+        debug_assert!(chunk_size == roundup_powerof2(chunk_size));
+        let chunk_mask = !(chunk_size as u64 - 1);
+        let chunk_size = chunk_size as usize;
+        let auto_buf_size = AutoBufferSize::with_per_mille(per_mille);
+        let end = file.seek(SeekFrom::End(0))?;
+        let max_num_chunks = (auto_buf_size.buffer_size(end)? / chunk_size) + 1;
+        file.rewind()?;
+        //
+        Ok(Self {
+            name: name.to_string(),
+            max_num_chunks,
+            chunk_size,
+            chunk_mask,
+            chunks: Vec::with_capacity(max_num_chunks),
+            map: OffsetIndex::with_capacity(max_num_chunks),
+            file,
+            pos: 0,
+            end,
+            fetch_cache: None,
+            #[cfg(feature = "buf_lru")]
+            uses_cnt: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_min_uses: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_max_uses: 0,
+            auto_buf_size: Some(auto_buf_size),
+            #[cfg(feature = "buf_print_hits")]
+            count_of_hits_fc: 0,
+            #[cfg(feature = "buf_print_hits")]
+            count_of_hits: 0,
+            #[cfg(feature = "buf_print_hits")]
+            count_of_miss: 0,
+            free_bufs: Vec::new(),
+            slices_buf: Vec::new(),
+            buf_align: 1,
+            bypass_threshold: 0,
+            pins: Vec::new(),
+            advices: Vec::new(),
+            poisoned: false,
+            dirty_top: Some(0),
+            write_fn,
+            next_data_fn: None,
+            #[cfg(feature = "buf_stats")]
+            stats_chunk_allocs: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_chunk_reuses: 0,
+            #[cfg(feature = "buf_stats")]
+            stats_hole_chunks: 0,
+        })
+    }
     #[cfg(feature = "buf_auto_buf_size")]
     #[inline]
     fn setup_auto_buf_size(&mut self) -> Result<()> {
@@ -1379,6 +2571,7 @@ impl<T: Seek + Read + Write> RaBuf<T> {
         self.fetch_chunk_0_(offset)
     }
     fn fetch_chunk_0_(&mut self, offset: u64) -> Result<&mut Chunk> {
+        self.check_poisoned_()?;
         let idx = if let Some(x) = self.map.get(&offset) {
             #[cfg(feature = "buf_print_hits")]
             {
@@ -1390,7 +2583,11 @@ impl<T: Seek + Read + Write> RaBuf<T> {
             {
                 self.count_of_miss += 1;
             }
-            self.add_chunk(offset)?
+            let idx = self.add_chunk(offset)?;
+            if !self.advices.is_empty() {
+                self.read_ahead_(offset)?;
+            }
+            idx
         };
         self.fetch_cache = Some((offset, idx));
         self.touch(idx);
@@ -1402,6 +2599,48 @@ impl<T: Seek + Read + Write> RaBuf<T> {
         Ok(chunk_mut)
     }
     //
+    /// Returns true if the file may be shorter than the chunk at `offset` by now.
+    /// It is, while the dirty chunks after it are not written yet, as after
+    /// `insert_bytes()`, `copy_within()` or a write past the end. The rest of
+    /// a short read is zero then, or else it is `UnexpectedEof`.
+    fn short_load_ok_(&mut self, offset: u64) -> bool {
+        let top = match self.dirty_top {
+            Some(x) => x,
+            None => {
+                let x = self
+                    .chunks
+                    .iter()
+                    .filter(|a| a.dirty)
+                    .map(|a| a.offset)
+                    .max()
+                    .unwrap_or(0);
+                self.dirty_top = Some(x);
+                x
+            }
+        };
+        top > offset
+    }
+    /// Returns the end for loading the chunk at `offset`. In the sparse mode,
+    /// it is `offset` for a chunk that has no data, so the chunk is made zero
+    /// without reading.
+    fn load_end_(&mut self, offset: u64) -> Result<u64> {
+        if let Some(next_data) = self.next_data_fn {
+            if offset < self.end {
+                let chunk_size = self.chunk_size as u64;
+                let data = next_data(&mut self.file, offset).map_err(|err| {
+                    RaBufError::new(&self.name, RaBufOp::Load, offset, chunk_size, err)
+                })?;
+                if data.map_or(true, |x| x >= offset + chunk_size) {
+                    #[cfg(feature = "buf_stats")]
+                    {
+                        self.stats_hole_chunks += 1;
+                    }
+                    return Ok(offset);
+                }
+            }
+        }
+        Ok(self.end)
+    }
     fn add_chunk(&mut self, offset: u64) -> Result<usize> {
         #[cfg(feature = "buf_auto_buf_size")]
         if self.chunks.len() == self.max_num_chunks {
@@ -1410,7 +2649,10 @@ impl<T: Seek + Read + Write> RaBuf<T> {
         self.fetch_cache = None;
         if self.chunks.len() < self.max_num_chunks {
             let new_idx = self.chunks.len();
-            match Chunk::new(offset, self.end, self.chunk_size, &mut self.file) {
+            let data = self.chunk_buffer();
+            let load_end = self.load_end_(offset)?;
+            let short_ok = self.short_load_ok_(offset);
+            match Chunk::new(&self.name, offset, load_end, short_ok, data, &mut self.file) {
                 Ok(x) => {
                     self.map.insert(&offset, new_idx);
                     self.chunks.push(x);
@@ -1428,23 +2670,32 @@ impl<T: Seek + Read + Write> RaBuf<T> {
             {
                 // LFU: Least Frequently Used
                 let min_idx = {
-                    // find the minimum uses counter.
+                    // find the minimum uses counter, except the pinned chunks.
                     let mut min_idx = 0;
-                    let mut min_uses = self.chunks[min_idx].uses;
-                    if min_uses != 0 {
-                        for i in 1..self.max_num_chunks {
-                            if self.chunks[i].uses < min_uses {
-                                min_idx = i;
-                                min_uses = self.chunks[min_idx].uses;
-                                if min_uses == 0 {
-                                    break;
-                                }
-                            } else {
-                                #[cfg(feature = "buf_stats")]
-                                {
-                                    if self.chunks[i].uses > self.stats_max_uses {
-                                        self.stats_max_uses = self.chunks[i].uses;
-                                    }
+                    let mut min_uses = u32::MAX;
+                    // the low priority chunks are evicted first.
+                    if let Some(i) = (0..self.max_num_chunks).find(|&i| {
+                        let offset = self.chunks[i].offset;
+                        self.is_low_priority(offset) && !self.is_pinned(offset)
+                    }) {
+                        min_idx = i;
+                        min_uses = 0;
+                    }
+                    for i in 0..self.max_num_chunks {
+                        if min_uses == 0 {
+                            break;
+                        }
+                        if self.is_pinned(self.chunks[i].offset) {
+                            continue;
+                        }
+                        if self.chunks[i].uses < min_uses {
+                            min_idx = i;
+                            min_uses = self.chunks[min_idx].uses;
+                        } else {
+                            #[cfg(feature = "buf_stats")]
+                            {
+                                if self.chunks[i].uses > self.stats_max_uses {
+                                    self.stats_max_uses = self.chunks[i].uses;
                                 }
                             }
                         }
@@ -1467,10 +2718,18 @@ impl<T: Seek + Read + Write> RaBuf<T> {
                     min_idx
                 };
                 // Make a new chunk, write the old chunk to disk, replace old chunk
-                self.chunks[min_idx].write(self.end, &mut self.file)?;
+                self.write_back_(min_idx)?;
                 self.map.remove(&self.chunks[min_idx].offset);
                 self.map.insert(&offset, min_idx);
-                self.chunks[min_idx].read_inplace(offset, self.end, &mut self.file)?;
+                let load_end = self.load_end_(offset)?;
+                let short_ok = self.short_load_ok_(offset);
+                self.chunks[min_idx].read_inplace(
+                    &self.name,
+                    offset,
+                    load_end,
+                    short_ok,
+                    &mut self.file,
+                )?;
                 #[cfg(feature = "buf_auto_buf_size")]
                 self.setup_auto_buf_size()?;
                 Ok(min_idx)
@@ -1480,6 +2739,9 @@ impl<T: Seek + Read + Write> RaBuf<T> {
     //
     #[cfg(all(feature = "buf_overf_rem", feature = "buf_overf_rem_all"))]
     fn remove_chunks(&mut self) -> Result<()> {
+        if self.remove_low_priority_chunks_()? {
+            return Ok(());
+        }
         self.clear()?;
         #[cfg(feature = "buf_auto_buf_size")]
         self.setup_auto_buf_size()?;
@@ -1487,11 +2749,15 @@ impl<T: Seek + Read + Write> RaBuf<T> {
     }
     #[cfg(all(feature = "buf_overf_rem", feature = "buf_overf_rem_half"))]
     fn remove_chunks(&mut self) -> Result<()> {
+        if self.remove_low_priority_chunks_()? {
+            return Ok(());
+        }
         // the LFU/LRU half clear
         let mut vec: Vec<(usize, u32)> = self
             .chunks
             .iter()
             .enumerate()
+            .filter(|(_idx, chunk)| !self.is_pinned(chunk.offset))
             .map(|(idx, chunk)| (idx, chunk.uses))
             .collect();
         vec.sort_by(|a, b| match b.1.cmp(&a.1) {
@@ -1499,12 +2765,14 @@ impl<T: Seek + Read + Write> RaBuf<T> {
             std::cmp::Ordering::Less => std::cmp::Ordering::Less,
             std::cmp::Ordering::Greater => std::cmp::Ordering::Greater,
         });
-        let half = vec.len() / 2;
+        let half = std::cmp::max(vec.len() / 2, 1);
         let _rest = vec.split_off(half);
         vec.sort_by(|a, b| a.0.cmp(&b.0));
         while let Some((idx, _uses)) = vec.pop() {
-            let mut _chunk = self.chunks.remove(idx);
-            _chunk.write(self.end, &mut self.file)?;
+            // writes before removing, so the chunk stays cached on failure.
+            self.write_back_(idx)?;
+            let chunk = self.chunks.remove(idx);
+            self.free_bufs.push(chunk.data);
         }
         self.map.clear();
         // clear all uses counter
@@ -1525,23 +2793,406 @@ impl<T: Seek + Read + Write> RaBuf<T> {
         }
         Ok(())
     }
+    /// Returns a chunk buffer, reusing a recycled one if there is.
+    /// The contents of a recycled buffer are not cleared.
+    fn chunk_buffer(&mut self) -> AlignedBuf {
+        if let Some(data) = self.free_bufs.pop() {
+            #[cfg(feature = "buf_stats")]
+            {
+                self.stats_chunk_reuses += 1;
+            }
+            data
+        } else {
+            #[cfg(feature = "buf_stats")]
+            {
+                self.stats_chunk_allocs += 1;
+            }
+            AlignedBuf::zeroed(self.chunk_size, self.buf_align)
+        }
+    }
+    /// Applies the access pattern hints to the chunk cache.
+    /// `len` zero means up to the end, as `posix_fadvise()`.
+    fn advise_cache_(&mut self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        let ed = if len == 0 {
+            u64::MAX
+        } else {
+            offset.saturating_add(len)
+        };
+        match advice {
+            Advice::WillNeed => {
+                // loads without evicting
+                let ed = ed.min(self.end);
+                let mut off = offset & self.chunk_mask;
+                while off < ed && self.chunks.len() < self.max_num_chunks {
+                    if self.map.get(&off).is_none() {
+                        let _ = self.add_chunk(off)?;
+                    }
+                    off += self.chunk_size as u64;
+                }
+            }
+            Advice::DontNeed => {
+                let st = offset & self.chunk_mask;
+                self.remove_chunks_in_(|_, off| st <= off && off < ed)?;
+            }
+            Advice::Normal | Advice::Sequential | Advice::Random | Advice::NoReuse => {
+                // replaces the hints of the range
+                let mut vec = Vec::with_capacity(self.advices.len() + 2);
+                for &(a, b, adv) in self.advices.iter() {
+                    if b <= offset || ed <= a {
+                        vec.push((a, b, adv));
+                    } else {
+                        if a < offset {
+                            vec.push((a, offset, adv));
+                        }
+                        if ed < b {
+                            vec.push((ed, b, adv));
+                        }
+                    }
+                }
+                if advice != Advice::Normal {
+                    vec.push((offset, ed, advice));
+                }
+                vec.sort_by_key(|a| a.0);
+                self.advices = vec;
+            }
+        }
+        Ok(())
+    }
+    /// Returns the access pattern hint at `offset`.
+    fn advice_at(&self, offset: u64) -> Advice {
+        for &(a, b, adv) in self.advices.iter() {
+            if offset < a {
+                break;
+            }
+            if offset < b {
+                return adv;
+            }
+        }
+        Advice::Normal
+    }
+    /// Returns true if the chunk at `offset` should be evicted before the others.
+    #[inline]
+    fn is_low_priority(&self, offset: u64) -> bool {
+        !self.advices.is_empty()
+            && matches!(self.advice_at(offset), Advice::Sequential | Advice::NoReuse)
+    }
+    /// Reads ahead the next chunk in the sequential range, if there is a room without eviction.
+    fn read_ahead_(&mut self, offset: u64) -> Result<()> {
+        let next = offset + self.chunk_size as u64;
+        if self.advice_at(offset) == Advice::Sequential
+            && self.advice_at(next) == Advice::Sequential
+            && next < self.end
+            && self.chunks.len() < self.max_num_chunks
+            && self.map.get(&next).is_none()
+        {
+            let _ = self.add_chunk(next)?;
+        }
+        Ok(())
+    }
+    /// Writes back and removes the low priority chunks.
+    /// Returns true if any chunk is removed.
+    #[cfg(feature = "buf_overf_rem")]
+    fn remove_low_priority_chunks_(&mut self) -> Result<bool> {
+        if self.advices.is_empty() {
+            return Ok(false);
+        }
+        let len = self.chunks.len();
+        self.remove_chunks_in_(|this, off| this.is_low_priority(off))?;
+        Ok(self.chunks.len() < len)
+    }
+    /// Writes back and removes the chunks that the offset matches `f`,
+    /// except the pinned chunks.
+    fn remove_chunks_in_<F: Fn(&Self, u64) -> bool>(&mut self, f: F) -> Result<()> {
+        let mut idx = self.chunks.len();
+        while idx > 0 {
+            idx -= 1;
+            let offset = self.chunks[idx].offset;
+            if !f(self, offset) || self.is_pinned(offset) {
+                continue;
+            }
+            self.write_back_(idx)?;
+            self.remove_chunk_(idx);
+        }
+        Ok(())
+    }
+    /// Writes all of the dirty chunks, and recovers from the poisoned state
+    /// when all of them are written.
+    fn flush_(&mut self) -> Result<()> {
+        let mut res = Ok(());
+        #[cfg(feature = "buf_hash_turbo")]
+        {
+            let mut off_vec: Vec<u64> = self.map.map.keys().copied().collect();
+            off_vec.sort_unstable();
+            for off in off_vec.iter() {
+                let idx = self.map.map[off];
+                if let Err(err) = self.write_back_(idx) {
+                    if res.is_ok() {
+                        res = Err(err);
+                    }
+                }
+            }
+        }
+        #[cfg(not(feature = "buf_hash_turbo"))]
+        {
+            for i in 0..self.map.vec.len() {
+                let idx = self.map.vec[i].1;
+                if let Err(err) = self.write_back_(idx) {
+                    if res.is_ok() {
+                        res = Err(err);
+                    }
+                }
+            }
+        }
+        if res.is_ok() {
+            self.poisoned = false;
+        }
+        res
+    }
+    /// Writes the chunk at `idx` if it is dirty.
+    /// On failure, the chunk stays dirty and the buffer is poisoned.
+    fn write_back_(&mut self, idx: usize) -> Result<()> {
+        let write_fn = match self.write_fn {
+            Some(x) => x,
+            // a read-only file has no dirty chunks.
+            None => return Ok(()),
+        };
+        #[cfg(feature = "buf_debug")]
+        let chunk = &mut self.chunks[idx];
+        #[cfg(not(feature = "buf_debug"))]
+        let chunk = unsafe { &mut *self.chunks.as_mut_ptr().add(idx) };
+        //
+        let was_top = chunk.dirty && Some(chunk.offset) == self.dirty_top;
+        let res = chunk.write(&self.name, self.end, &mut self.file, write_fn);
+        if res.is_err() {
+            self.poisoned = true;
+            // the fast path of `fetch_chunk()` must check the poisoned state.
+            self.fetch_cache = None;
+        } else if was_top {
+            self.dirty_top = None;
+        }
+        res
+    }
+    /// Returns an error if the buffer is poisoned.
+    #[inline]
+    fn check_poisoned_(&self) -> Result<()> {
+        if self.poisoned {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "rabuf \"{}\": poisoned by a failed write-back, flush() or take_unwritten()",
+                    self.name
+                ),
+            ));
+        }
+        Ok(())
+    }
+    /// Removes the chunk at `idx` and recycles its buffer.
+    fn remove_chunk_(&mut self, idx: usize) {
+        let chunk = self.chunks.swap_remove(idx);
+        self.map.remove(&chunk.offset);
+        if chunk.dirty && Some(chunk.offset) == self.dirty_top {
+            self.dirty_top = None;
+        }
+        if idx < self.chunks.len() {
+            let moved = self.chunks[idx].offset;
+            self.map.insert(&moved, idx);
+        }
+        self.fetch_cache = None;
+        self.free_bufs.push(chunk.data);
+    }
+    /// Reads `buf` at `offset` through the chunks, without moving the position.
+    fn read_at_(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            let curr = offset + done as u64;
+            let chunk = self.fetch_chunk(curr)?;
+            let st = (curr - chunk.offset) as usize;
+            let n = (chunk.data.len() - st).min(buf.len() - done);
+            buf[done..(done + n)].copy_from_slice(&chunk.data[st..(st + n)]);
+            done += n;
+        }
+        Ok(())
+    }
+    /// Returns the indices of the cached chunks that overlap the range, sorted by the offset.
+    fn overlapping_chunks(&self, offset: u64, len: u64) -> Vec<usize> {
+        let st = offset & self.chunk_mask;
+        let ed = offset.saturating_add(len);
+        let mut vec: Vec<usize> = (0..self.chunks.len())
+            .filter(|&idx| {
+                let off = self.chunks[idx].offset;
+                len > 0 && st <= off && off < ed
+            })
+            .collect();
+        vec.sort_by_key(|&idx| self.chunks[idx].offset);
+        vec
+    }
+    /// Returns the offsets of the chunks that cover the range.
+    fn covering_chunks(&self, offset: u64, len: u64) -> Result<Vec<u64>> {
+        let mut vec = Vec::new();
+        if len > 0 {
+            let ed = range_end(offset, len)?;
+            let mut off = offset & self.chunk_mask;
+            while off < ed {
+                vec.push(off);
+                off = match off.checked_add(self.chunk_size as u64) {
+                    Some(x) => x,
+                    None => break,
+                };
+            }
+        }
+        Ok(vec)
+    }
+    /// Returns the index range of the pins in `st..ed`.
+    fn pins_in_(&self, st: u64, ed: u64) -> std::ops::Range<usize> {
+        let lo = self.pins.partition_point(|a| a.0 < st);
+        let hi = self.pins.partition_point(|a| a.0 < ed);
+        lo..hi
+    }
+    /// Returns true if the chunk at `offset` is pinned.
+    #[inline]
+    fn is_pinned(&self, offset: u64) -> bool {
+        !self.pins.is_empty() && self.pins.binary_search_by(|a| a.0.cmp(&offset)).is_ok()
+    }
+    /// Reads the fully covered chunks of a large transfer, without fetching uncached chunks.
+    /// Returns `None` if `buf` does not cover any whole chunk from the current position.
+    #[inline(never)]
+    fn read_bypass_(&mut self, buf: &mut [u8]) -> Result<Option<usize>> {
+        self.check_poisoned_()?;
+        let chunk_size = self.chunk_size as u64;
+        let curr = self.pos;
+        if curr & !self.chunk_mask != 0 || curr >= self.end {
+            return Ok(None);
+        }
+        let len = (buf.len() as u64).min(self.end - curr) & self.chunk_mask;
+        if len == 0 {
+            return Ok(None);
+        }
+        let mut done = 0;
+        while done < len {
+            let offset = curr + done;
+            let st = done as usize;
+            if let Some(idx) = self.map.get(&offset) {
+                let chunk = &self.chunks[idx];
+                buf[st..(st + self.chunk_size)].copy_from_slice(&chunk.data);
+                done += chunk_size;
+                continue;
+            }
+            // a run of uncached chunks
+            let mut run = chunk_size;
+            while done + run < len && self.map.get(&(offset + run)).is_none() {
+                run += chunk_size;
+            }
+            let ed = st + run as usize;
+            let short_ok = self.short_load_ok_(offset);
+            let load_err = |err| RaBufError::new(&self.name, RaBufOp::Load, offset, run, err);
+            self.file.seek(SeekFrom::Start(offset)).map_err(load_err)?;
+            read_load(&mut self.file, &mut buf[st..ed], short_ok).map_err(load_err)?;
+            done += run;
+        }
+        self.pos += len;
+        Ok(Some(len as usize))
+    }
     //
     #[inline(never)]
     fn read_exact_maybeslice_vec_(&mut self, size: usize) -> Result<MaybeSlice<'_>> {
+        // do not allocate a huge buffer for a broken size.
+        if self.pos > self.end || size as u64 > self.end - self.pos {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
         let mut buf = vec![0u8; size];
         self.read_exact(&mut buf)?;
         Ok(MaybeSlice::Buffer(buf))
     }
+}
+
+impl<T: Seek + Read + Write> RaBuf<T> {
+    /// Raises the offset of the highest dirty chunk to the chunk at `offset`.
+    #[inline]
+    fn mark_dirty_(&mut self, offset: u64) {
+        if let Some(top) = self.dirty_top {
+            let chunk_off = offset & self.chunk_mask;
+            if top < chunk_off {
+                self.dirty_top = Some(chunk_off);
+            }
+        }
+    }
+    /// Writes `buf` at `offset` into the chunks, without moving the position
+    /// nor extending the end.
+    fn write_at_(&mut self, offset: u64, buf: &[u8]) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            let curr = offset + done as u64;
+            let chunk = self.fetch_chunk(curr)?;
+            let st = (curr - chunk.offset) as usize;
+            let n = (chunk.data.len() - st).min(buf.len() - done);
+            chunk.data[st..(st + n)].copy_from_slice(&buf[done..(done + n)]);
+            chunk.dirty = true;
+            self.mark_dirty_(curr);
+            done += n;
+        }
+        Ok(())
+    }
+    /// Writes the fully covered chunks of a large transfer, without fetching uncached chunks.
+    /// Returns `None` if `buf` does not cover any whole chunk from the current position.
+    #[inline(never)]
+    fn write_bypass_(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        self.check_poisoned_()?;
+        let chunk_size = self.chunk_size as u64;
+        let curr = self.pos;
+        if curr & !self.chunk_mask != 0 {
+            return Ok(None);
+        }
+        let len = buf.len() as u64 & self.chunk_mask;
+        if len == 0 {
+            return Ok(None);
+        }
+        let mut done = 0;
+        while done < len {
+            let offset = curr + done;
+            let st = done as usize;
+            if let Some(idx) = self.map.get(&offset) {
+                let chunk = &mut self.chunks[idx];
+                chunk.data.copy_from_slice(&buf[st..(st + self.chunk_size)]);
+                chunk.dirty = true;
+                self.mark_dirty_(offset);
+                done += chunk_size;
+                continue;
+            }
+            // a run of uncached chunks
+            let mut run = chunk_size;
+            while done + run < len && self.map.get(&(offset + run)).is_none() {
+                run += chunk_size;
+            }
+            let write_err = |err| RaBufError::new(&self.name, RaBufOp::WriteBack, offset, run, err);
+            self.file.seek(SeekFrom::Start(offset)).map_err(write_err)?;
+            self.file
+                .write_all(&buf[st..(st + run as usize)])
+                .map_err(write_err)?;
+            done += run;
+        }
+        self.pos += len;
+        if self.end < self.pos {
+            self.end = self.pos;
+        }
+        Ok(Some(len as usize))
+    }
     #[inline(never)]
     fn write_zero_0_(&mut self, size: usize) -> Result<()> {
-        let buf = vec![0u8; size];
-        self.write_all(&buf)
+        let curr = self.pos;
+        self.fill(curr, size as u64, &[0u8])?;
+        self.pos += size as u64;
+        Ok(())
     }
 }
 
-impl<T: Seek + Read + Write> Read for RaBuf<T> {
+impl<T: Seek + Read> Read for RaBuf<T> {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.bypass_threshold > 0 && buf.len() >= self.bypass_threshold {
+            if let Some(len) = self.read_bypass_(buf)? {
+                return Ok(len);
+            }
+        }
         let curr = self.pos;
         let ed = self.end;
         let len = {
@@ -1595,9 +3246,33 @@ impl<T: Seek + Read + Write> Read for RaBuf<T> {
     }
 }
 
+impl<T: Seek + Read> BufRead for RaBuf<T> {
+    /// Returns the rest of the current chunk, up to the end.
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        let curr = self.pos;
+        let ed = self.end;
+        if curr >= ed {
+            return Ok(&[]);
+        }
+        let chunk = self.fetch_chunk(curr)?;
+        let st = (curr - chunk.offset) as usize;
+        let ed = ((ed - chunk.offset) as usize).min(chunk.data.len());
+        Ok(&chunk.data[st..ed])
+    }
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt as u64;
+    }
+}
+
 impl<T: Seek + Read + Write> Write for RaBuf<T> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.bypass_threshold > 0 && buf.len() >= self.bypass_threshold {
+            if let Some(len) = self.write_bypass_(buf)? {
+                return Ok(len);
+            }
+        }
         let curr = self.pos;
         let len = {
             let chunk = self.fetch_chunk(curr)?;
@@ -1635,48 +3310,30 @@ impl<T: Seek + Read + Write> Write for RaBuf<T> {
                 data_slice_len
             }
         };
+        self.mark_dirty_(curr);
         self.pos += len as u64;
         if self.end < self.pos {
             self.end = self.pos;
         }
         Ok(len)
     }
+    /// Writes all of the dirty chunks.
+    ///
+    /// Every dirty chunk is tried even if some of them fail, and the first
+    /// error is returned. The buffer recovers from the poisoned state when
+    /// all of them are written.
     #[inline]
     fn flush(&mut self) -> Result<()> {
-        #[cfg(feature = "buf_hash_turbo")]
-        {
-            let mut off_vec: Vec<u64> = self.map.map.keys().copied().collect();
-            off_vec.sort_unstable();
-            for off in off_vec.iter() {
-                let idx = self.map.map[off];
-                //
-                #[cfg(feature = "buf_debug")]
-                let chunk = &mut self.chunks[idx];
-                #[cfg(not(feature = "buf_debug"))]
-                let chunk = unsafe { &mut *self.chunks.as_mut_ptr().add(idx) };
-                //
-                chunk.write(self.end, &mut self.file)?;
-            }
-        }
-        #[cfg(not(feature = "buf_hash_turbo"))]
-        {
-            for &(_, idx) in self.map.vec.iter() {
-                #[cfg(feature = "buf_debug")]
-                let chunk = &mut self.chunks[idx];
-                #[cfg(not(feature = "buf_debug"))]
-                let chunk = unsafe { &mut *self.chunks.as_mut_ptr().add(idx) };
-                //
-                chunk.write(self.end, &mut self.file)?;
-            }
-        }
-        Ok(())
+        self.flush_()
     }
 }
 
-impl<T: Seek + Read + Write> Drop for RaBuf<T> {
+impl<T: Seek + Read> Drop for RaBuf<T> {
     /// Write all of the chunks to disk before closing the file.
     fn drop(&mut self) {
-        let _ = self.flush();
+        if let Err(err) = self.flush_() {
+            eprintln!("rabuf \"{}\": flush on drop failed: {}", self.name, err);
+        }
         #[cfg(feature = "buf_print_hits")]
         {
             let all = self.count_of_hits + self.count_of_miss;
@@ -1707,26 +3364,29 @@ mod debug {
             {
                 #[cfg(not(feature = "buf_stats"))]
                 {
-                    assert_eq!(std::mem::size_of::<BufFile>(), 144);
+                    #[cfg(not(feature = "buf_lru"))]
+                    assert_eq!(std::mem::size_of::<BufFile>(), 288);
+                    #[cfg(feature = "buf_lru")]
+                    assert_eq!(std::mem::size_of::<BufFile>(), 296);
                 }
                 #[cfg(feature = "buf_stats")]
-                assert_eq!(std::mem::size_of::<BufFile>(), 128);
+                assert_eq!(std::mem::size_of::<BufFile>(), 320);
             }
             #[cfg(feature = "buf_hash_turbo")]
             {
                 #[cfg(not(feature = "buf_myhash"))]
                 {
                     #[cfg(not(feature = "buf_stats"))]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 192);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 336);
                     #[cfg(feature = "buf_stats")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 200);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 360);
                 }
                 #[cfg(feature = "buf_myhash")]
                 {
                     #[cfg(not(feature = "buf_stats"))]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 152);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 304);
                     #[cfg(feature = "buf_stats")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 184);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 336);
                 }
             }
             //
@@ -1747,44 +3407,44 @@ mod debug {
                             feature = "buf_overf_rem_all",
                             feature = "buf_overf_rem_half"
                         )))]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 80);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 128);
                         #[cfg(feature = "buf_overf_rem_half")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 76);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 124);
                         #[cfg(feature = "buf_overf_rem_all")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 92);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 140);
                     }
                     #[cfg(target_arch = "arm")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 96);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 144);
                 }
                 #[cfg(all(feature = "buf_stats", feature = "buf_lru"))]
                 {
                     #[cfg(not(target_arch = "arm"))]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 88);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 136);
                     #[cfg(target_arch = "arm")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 96);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 144);
                 }
                 #[cfg(all(feature = "buf_stats", not(feature = "buf_lru")))]
                 {
                     #[cfg(not(target_arch = "arm"))]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 84);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 132);
                     #[cfg(target_arch = "arm")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 96);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 144);
                 }
                 #[cfg(all(not(feature = "buf_stats"), feature = "buf_lru"))]
                 {
                     #[cfg(not(target_arch = "arm"))]
                     {
                         #[cfg(not(feature = "buf_overf_rem_half"))]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 80);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 128);
                         #[cfg(feature = "buf_overf_rem_half")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 92);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 140);
                     }
                     #[cfg(target_arch = "arm")]
                     {
                         #[cfg(not(feature = "buf_overf_rem_half"))]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 80);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 128);
                         #[cfg(feature = "buf_overf_rem_half")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 104);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 152);
                     }
                 }
             }
@@ -1798,53 +3458,53 @@ mod debug {
                             feature = "buf_overf_rem_all",
                             feature = "buf_overf_rem_half"
                         )))]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 80);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 128);
                         #[cfg(feature = "buf_overf_rem_half")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 76);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 124);
                         #[cfg(feature = "buf_overf_rem_all")]
                         {
                             #[cfg(target_pointer_width = "64")]
                             {
-                                assert_eq!(std::mem::size_of::<BufFile>(), 124);
+                                assert_eq!(std::mem::size_of::<BufFile>(), 172);
                             }
                             #[cfg(target_pointer_width = "32")]
                             {
-                                assert_eq!(std::mem::size_of::<BufFile>(), 96);
+                                assert_eq!(std::mem::size_of::<BufFile>(), 144);
                             }
                         }
                     }
                     #[cfg(target_arch = "arm")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 136);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 184);
                 }
                 #[cfg(all(feature = "buf_stats", feature = "buf_lru"))]
                 {
                     #[cfg(not(target_arch = "arm"))]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 88);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 136);
                     #[cfg(target_arch = "arm")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 96);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 144);
                 }
                 #[cfg(all(feature = "buf_stats", not(feature = "buf_lru")))]
                 {
                     #[cfg(not(target_arch = "arm"))]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 132);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 180);
                     #[cfg(target_arch = "arm")]
-                    assert_eq!(std::mem::size_of::<BufFile>(), 144);
+                    assert_eq!(std::mem::size_of::<BufFile>(), 192);
                 }
                 #[cfg(all(not(feature = "buf_stats"), feature = "buf_lru"))]
                 {
                     #[cfg(not(target_arch = "arm"))]
                     {
                         #[cfg(not(feature = "buf_overf_rem_half"))]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 80);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 128);
                         #[cfg(feature = "buf_overf_rem_half")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 92);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 140);
                     }
                     #[cfg(target_arch = "arm")]
                     {
                         #[cfg(not(feature = "buf_overf_rem_half"))]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 80);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 128);
                         #[cfg(feature = "buf_overf_rem_half")]
-                        assert_eq!(std::mem::size_of::<BufFile>(), 104);
+                        assert_eq!(std::mem::size_of::<BufFile>(), 152);
                     }
                 }
             }
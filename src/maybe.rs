@@ -1,3 +1,4 @@
+use std::io::{Read, Result};
 use std::ops::Deref;
 
 /// this is buffer, but maybe slice.
@@ -26,3 +27,142 @@ impl Deref for MaybeSlice<'_> {
         }
     }
 }
+
+/// this is a rope of slices, one per chunk.
+///
+/// The bytes are read with `Read`, or the remaining pieces are taken with
+/// `Iterator`. The comparison is on the remaining bytes, regardless of
+/// the boundaries of the pieces.
+#[derive(Debug, Clone, Default)]
+pub struct MaybeSlices<'a> {
+    pieces: Vec<&'a [u8]>,
+    /// The current piece.
+    idx: usize,
+    /// The position in the current piece.
+    st: usize,
+}
+
+impl<'a> MaybeSlices<'a> {
+    pub(crate) fn new(pieces: Vec<&'a [u8]>) -> Self {
+        Self {
+            pieces,
+            idx: 0,
+            st: 0,
+        }
+    }
+    /// The length of the remaining bytes.
+    pub fn len(&self) -> usize {
+        self.remaining().map(|x| x.len()).sum()
+    }
+    /// Returns true if no byte remains.
+    pub fn is_empty(&self) -> bool {
+        self.remaining().all(|x| x.is_empty())
+    }
+    /// The number of the remaining pieces.
+    pub fn num_pieces(&self) -> usize {
+        self.pieces.len() - self.idx
+    }
+    /// Copies the remaining bytes into a vec.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(self.len());
+        for x in self.remaining() {
+            vec.extend_from_slice(x);
+        }
+        vec
+    }
+    fn remaining(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        let st = self.st;
+        self.pieces[self.idx..]
+            .iter()
+            .enumerate()
+            .map(move |(i, &x)| if i == 0 { &x[st..] } else { x })
+    }
+}
+
+impl<'a> Iterator for MaybeSlices<'a> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let piece = self.pieces.get(self.idx)?;
+        let r = &piece[self.st..];
+        self.idx += 1;
+        self.st = 0;
+        Some(r)
+    }
+}
+
+impl Read for MaybeSlices<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut done = 0;
+        while done < buf.len() && self.idx < self.pieces.len() {
+            let piece = &self.pieces[self.idx][self.st..];
+            let n = piece.len().min(buf.len() - done);
+            buf[done..(done + n)].copy_from_slice(&piece[..n]);
+            done += n;
+            self.st += n;
+            if self.st == self.pieces[self.idx].len() {
+                self.idx += 1;
+                self.st = 0;
+            }
+        }
+        Ok(done)
+    }
+}
+
+/// Compares the byte streams chunk by chunk.
+fn eq_pieces<'a, 'b, A, B>(mut a: A, mut b: B) -> bool
+where
+    A: Iterator<Item = &'a [u8]>,
+    B: Iterator<Item = &'b [u8]>,
+{
+    let mut xa: &[u8] = &[];
+    let mut xb: &[u8] = &[];
+    loop {
+        while xa.is_empty() {
+            match a.next() {
+                Some(x) => xa = x,
+                None => break,
+            }
+        }
+        while xb.is_empty() {
+            match b.next() {
+                Some(x) => xb = x,
+                None => break,
+            }
+        }
+        if xa.is_empty() || xb.is_empty() {
+            return xa.is_empty() && xb.is_empty();
+        }
+        let n = xa.len().min(xb.len());
+        if xa[..n] != xb[..n] {
+            return false;
+        }
+        xa = &xa[n..];
+        xb = &xb[n..];
+    }
+}
+
+impl PartialEq for MaybeSlices<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        eq_pieces(self.remaining(), other.remaining())
+    }
+}
+
+impl Eq for MaybeSlices<'_> {}
+
+impl PartialEq<[u8]> for MaybeSlices<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        eq_pieces(self.remaining(), std::iter::once(other))
+    }
+}
+
+impl PartialEq<&[u8]> for MaybeSlices<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<Vec<u8>> for MaybeSlices<'_> {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        *self == other[..]
+    }
+}
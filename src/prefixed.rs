@@ -0,0 +1,122 @@
+/*!
+Length-prefixed bytes and strings.
+
+`write_bytes_prefixed()` of [`RaBuf`](super::RaBuf) writes the length of
+the bytes in the [`LenWidth`] encoding, followed by the bytes.
+`read_bytes_prefixed()` reads them back as [`MaybeSlice`](super::MaybeSlice),
+that is a slice of the chunk when the bytes lie in one chunk.
+
+`write_str_prefixed()` and `read_str_prefixed()` are the string variants.
+The read validates UTF-8 and returns `Cow::Borrowed` when the string lies in
+one chunk.
+
+A length beyond the end of the file is an `UnexpectedEof` error, and no
+buffer is allocated for it.
+
+# Examples
+
+```rust
+use rabuf::{BufFile, LenWidth};
+use std::io::{Seek, SeekFrom};
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open("target/tmp/doc_test_prefixed").unwrap();
+let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+bf.write_bytes_prefixed(b"ABC", LenWidth::U16).unwrap();
+bf.write_str_prefixed("hello", LenWidth::Varint).unwrap();
+bf.seek(SeekFrom::Start(0)).unwrap();
+assert_eq!(&*bf.read_bytes_prefixed(LenWidth::U16).unwrap(), b"ABC");
+assert_eq!(bf.read_str_prefixed(LenWidth::Varint).unwrap(), "hello");
+```
+*/
+use super::{SmallRead, SmallWrite};
+use std::io::{Result, Write};
+
+/// The encoding of the length prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LenWidth {
+    /// one byte.
+    U8,
+    /// 2 bytes, little endian.
+    U16,
+    /// 4 bytes, little endian.
+    U32,
+    /// 8 bytes, little endian.
+    U64,
+    /// unsigned LEB128, 1 to 10 bytes.
+    Varint,
+}
+
+impl LenWidth {
+    /// The maximum length that can be encoded.
+    pub fn max_len(&self) -> u64 {
+        match self {
+            LenWidth::U8 => u8::MAX as u64,
+            LenWidth::U16 => u16::MAX as u64,
+            LenWidth::U32 => u32::MAX as u64,
+            LenWidth::U64 | LenWidth::Varint => u64::MAX,
+        }
+    }
+}
+
+/// Writes the length in the encoding of `width`.
+pub(crate) fn write_len<W: SmallWrite + Write + ?Sized>(
+    w: &mut W,
+    len: u64,
+    width: LenWidth,
+) -> Result<()> {
+    if len > width.max_len() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("too large length for {:?}: {}", width, len),
+        ));
+    }
+    match width {
+        LenWidth::U8 => w.write_u8(len as u8),
+        LenWidth::U16 => w.write_u16_le(len as u16),
+        LenWidth::U32 => w.write_u32_le(len as u32),
+        LenWidth::U64 => w.write_u64_le(len),
+        LenWidth::Varint => {
+            let mut buf = [0u8; 10];
+            let mut n = 0;
+            let mut v = len;
+            while v >= 0x80 {
+                buf[n] = (v as u8) | 0x80;
+                v >>= 7;
+                n += 1;
+            }
+            buf[n] = v as u8;
+            w.write_all(&buf[..=n])
+        }
+    }
+}
+
+/// Reads the length in the encoding of `width`.
+pub(crate) fn read_len<R: SmallRead + ?Sized>(r: &mut R, width: LenWidth) -> Result<u64> {
+    match width {
+        LenWidth::U8 => Ok(r.read_u8()? as u64),
+        LenWidth::U16 => Ok(r.read_u16_le()? as u64),
+        LenWidth::U32 => Ok(r.read_u32_le()? as u64),
+        LenWidth::U64 => r.read_u64_le(),
+        LenWidth::Varint => {
+            let mut val = 0u64;
+            let mut shift = 0;
+            loop {
+                let b = r.read_u8()?;
+                if shift == 63 && b > 1 {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "varint overflows u64",
+                    ));
+                }
+                val |= ((b & 0x7f) as u64) << shift;
+                if b & 0x80 == 0 {
+                    return Ok(val);
+                }
+                shift += 7;
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+/*!
+Read-only buffer over `Read + Seek` sources.
+
+[`RaReader`] has the same chunk cache and [`SmallRead`] fast routines as
+[`RaBuf`], but it does not need `Write` of the source. So it can be used
+for read-only files, `Cursor<&[u8]>`, and the other readers with `Seek`.
+The inner [`RaBuf`] is made without `Write::write_all()` of the source, and
+the write methods need `T: Write`, so the chunks are never dirty and there
+is no write-back.
+
+# Examples
+
+```rust
+use rabuf::{RaReader, SmallRead};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+let data = b"ABCEDFG\nhijklmn\n";
+let mut rr = RaReader::with_capacity("tes", Cursor::new(&data[..]), 4, 2).unwrap();
+rr.seek(SeekFrom::Start(8)).unwrap();
+assert_eq!(rr.read_u8().unwrap(), b'h');
+let mut buf = Vec::new();
+rr.read_to_end(&mut buf).unwrap();
+assert_eq!(&buf, b"ijklmn\n");
+```
+*/
+use super::{seek_pos, MaybeSlice, RaBuf, SmallRead};
+use std::io::{Read, Result, Seek, SeekFrom};
+
+/// Read-only buffer for random access.
+#[derive(Debug)]
+pub struct RaReader<T: Read + Seek> {
+    buf: RaBuf<T>,
+}
+
+impl<T: Read + Seek> RaReader<T> {
+    /// Creates a new RaReader.
+    /// number of chunk: 16, chunk size: 4096
+    pub fn new(name: &str, file: T) -> Result<RaReader<T>> {
+        Ok(Self {
+            buf: RaBuf::new_(name, file, None)?,
+        })
+    }
+    /// Creates a new RaReader with the specified number of chunks.
+    /// chunk_size is MUST power of 2.
+    pub fn with_capacity(
+        name: &str,
+        file: T,
+        chunk_size: u32,
+        max_num_chunks: u16,
+    ) -> Result<RaReader<T>> {
+        Ok(Self {
+            buf: RaBuf::with_capacity_(name, file, chunk_size, max_num_chunks, None)?,
+        })
+    }
+    /// Clear all buffer chunks.
+    #[inline]
+    pub fn clear(&mut self) -> Result<()> {
+        self.buf.clear()
+    }
+    /// Name for debugging
+    #[inline]
+    pub fn name(&self) -> String {
+        self.buf.name()
+    }
+    /// make preparation
+    #[inline]
+    pub fn prepare(&mut self, offset: u64) -> Result<()> {
+        self.buf.prepare(offset)
+    }
+    /// Pins the chunks that cover the range, and loads them.
+    /// See [`RaBuf::pin_range()`].
+    #[inline]
+    pub fn pin_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        self.buf.pin_range(offset, len)
+    }
+    /// Unpins the chunks that cover the range.
+    #[inline]
+    pub fn unpin_range(&mut self, offset: u64, len: u64) {
+        self.buf.unpin_range(offset, len)
+    }
+    /// Sets the size in bytes from which `read()` bypasses the chunk cache.
+    /// See [`RaBuf::set_bypass_threshold()`].
+    #[inline]
+    pub fn set_bypass_threshold(&mut self, threshold: usize) {
+        self.buf.set_bypass_threshold(threshold)
+    }
+    /// The length of the source.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.buf.end
+    }
+    /// Returns true if the source is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buf.end == 0
+    }
+    /// Gets a reference to the source.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.buf.file
+    }
+    /// Returns true if `size` bytes can be read from the current position.
+    #[inline]
+    fn has_bytes(&self, size: usize) -> bool {
+        self.buf.pos <= self.buf.end && size as u64 <= self.buf.end - self.buf.pos
+    }
+}
+
+impl<T: Read + Seek> Read for RaReader<T> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.buf.pos >= self.buf.end {
+            return Ok(0);
+        }
+        let rest = self.buf.end - self.buf.pos;
+        if (buf.len() as u64) > rest {
+            let rest = rest as usize;
+            return self.buf.read(&mut buf[..rest]);
+        }
+        self.buf.read(buf)
+    }
+}
+
+impl<T: Read + Seek> Seek for RaReader<T> {
+    /// Seeks to the position. The position beyond the end is allowed,
+    /// and the reads from there return no bytes.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.buf.pos = seek_pos(self.buf.pos, self.buf.end, pos)?;
+        Ok(self.buf.pos)
+    }
+}
+
+// the fast routines of `RaBuf` do not check the end, because the writes extend the file.
+// here the reads over the end go through `read_exact()`, that returns `UnexpectedEof`.
+impl<T: Read + Seek> SmallRead for RaReader<T> {
+    #[inline]
+    fn read_u8(&mut self) -> Result<u8> {
+        if self.has_bytes(1) {
+            self.buf.read_u8()
+        } else {
+            let mut buf = [0u8; 1];
+            self.read_exact(&mut buf)?;
+            Ok(buf[0])
+        }
+    }
+    #[inline]
+    fn read_u16_le(&mut self) -> Result<u16> {
+        if self.has_bytes(2) {
+            self.buf.read_u16_le()
+        } else {
+            let mut buf = [0u8; 2];
+            self.read_exact(&mut buf)?;
+            Ok(u16::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_u32_le(&mut self) -> Result<u32> {
+        if self.has_bytes(4) {
+            self.buf.read_u32_le()
+        } else {
+            let mut buf = [0u8; 4];
+            self.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_u64_le(&mut self) -> Result<u64> {
+        if self.has_bytes(8) {
+            self.buf.read_u64_le()
+        } else {
+            let mut buf = [0u8; 8];
+            self.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_max_8_bytes(&mut self, size: usize) -> Result<u64> {
+        debug_assert!(size <= 8, "size: {} <= 8", size);
+        if self.has_bytes(size) {
+            self.buf.read_max_8_bytes(size)
+        } else {
+            let mut buf = [0u8; 8];
+            self.read_exact(&mut buf[..size])?;
+            Ok(u64::from_le_bytes(buf))
+        }
+    }
+    #[inline]
+    fn read_exact_small(&mut self, buf: &mut [u8]) -> Result<()> {
+        if self.has_bytes(buf.len()) {
+            self.buf.read_exact_small(buf)
+        } else {
+            self.read_exact(buf)
+        }
+    }
+    #[inline]
+    fn read_exact_maybeslice(&mut self, size: usize) -> Result<MaybeSlice<'_>> {
+        if self.has_bytes(size) {
+            self.buf.read_exact_maybeslice(size)
+        } else {
+            // do not allocate a huge buffer for a broken size.
+            Err(std::io::ErrorKind::UnexpectedEof.into())
+        }
+    }
+}
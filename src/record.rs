@@ -0,0 +1,204 @@
+/*!
+Fixed-size record file on top of [`RaBuf`].
+
+[`RecordFile`] maps a record index to the offset in the file, and encodes
+and decodes the records with [`SmallRead`] and [`SmallWrite`].
+
+With [`RecordLayout::NoStraddle`], the records are laid out so that they
+never straddle a chunk boundary: a chunk holds `chunk_size / SIZE` records,
+and the rest of the chunk is padding. So every record is read and written
+with the fast routines in one chunk.
+
+# Examples
+
+```rust
+use rabuf::{BufFile, Record, RecordFile, RecordLayout, SmallRead, SmallWrite};
+use std::io::Result;
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+impl Record for Point {
+    const SIZE: usize = 8;
+    fn encode<W: SmallWrite + ?Sized>(&self, w: &mut W) -> Result<()> {
+        w.write_u32_le(self.x)?;
+        w.write_u32_le(self.y)
+    }
+    fn decode<S: SmallRead + ?Sized>(r: &mut S) -> Result<Self> {
+        Ok(Point {
+            x: r.read_u32_le()?,
+            y: r.read_u32_le()?,
+        })
+    }
+}
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open("target/tmp/doc_test_record").unwrap();
+let bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+let mut rf = RecordFile::<Point, _>::new(bf, RecordLayout::NoStraddle).unwrap();
+rf.push(&Point { x: 1, y: 2 }).unwrap();
+rf.push(&Point { x: 3, y: 4 }).unwrap();
+assert_eq!(rf.len(), 2);
+assert_eq!(rf.get(1).unwrap(), Point { x: 3, y: 4 });
+```
+*/
+use super::{FileSetLen, RaBuf, SmallRead, SmallWrite};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+
+/// A record with a fixed byte size.
+pub trait Record: Sized {
+    /// The encoded size in bytes.
+    const SIZE: usize;
+    /// Writes `SIZE` bytes of the record.
+    fn encode<W: SmallWrite + ?Sized>(&self, w: &mut W) -> Result<()>;
+    /// Reads `SIZE` bytes of a record.
+    fn decode<S: SmallRead + ?Sized>(r: &mut S) -> Result<Self>;
+}
+
+/// The layout of the records in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordLayout {
+    /// The records are packed without padding.
+    Packed,
+    /// The records never straddle a chunk boundary.
+    /// `SIZE` MUST be less than or equal to the chunk size.
+    NoStraddle,
+}
+
+/// Fixed-size record file.
+#[derive(Debug)]
+pub struct RecordFile<R: Record, T: Seek + Read + Write + FileSetLen> {
+    buf: RaBuf<T>,
+    /// The number of records per chunk, or None if packed.
+    per_chunk: Option<u64>,
+    /// The number of records.
+    len: u64,
+    _record: PhantomData<R>,
+}
+
+impl<R: Record, T: Seek + Read + Write + FileSetLen> RecordFile<R, T> {
+    /// Creates a new RecordFile on the buffer.
+    /// The number of records is computed from the length of the file.
+    pub fn new(buf: RaBuf<T>, layout: RecordLayout) -> Result<RecordFile<R, T>> {
+        if R::SIZE == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "record size is zero",
+            ));
+        }
+        let chunk_size = buf.chunk_size as u64;
+        let size = R::SIZE as u64;
+        let per_chunk = match layout {
+            RecordLayout::Packed => None,
+            RecordLayout::NoStraddle => {
+                if size > chunk_size {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("record size {} is over the chunk size {}", size, chunk_size),
+                    ));
+                }
+                Some(chunk_size / size)
+            }
+        };
+        let end = buf.end;
+        let len = match per_chunk {
+            None => end / size,
+            Some(n) => (end / chunk_size) * n + (end % chunk_size) / size,
+        };
+        Ok(Self {
+            buf,
+            per_chunk,
+            len,
+            _record: PhantomData,
+        })
+    }
+    /// The number of records.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    /// Returns true if there is no record.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Reads the record at `idx`.
+    pub fn get(&mut self, idx: u64) -> Result<R> {
+        self.check_index(idx)?;
+        self.buf.seek(SeekFrom::Start(self.offset_of(idx)))?;
+        R::decode(&mut self.buf)
+    }
+    /// Writes the record at `idx`.
+    pub fn set(&mut self, idx: u64, record: &R) -> Result<()> {
+        self.check_index(idx)?;
+        self.write_at(idx, record)
+    }
+    /// Appends the record at the end.
+    pub fn push(&mut self, record: &R) -> Result<()> {
+        self.write_at(self.len, record)?;
+        self.len += 1;
+        Ok(())
+    }
+    /// Shortens to `len` records, and truncates the file.
+    /// It has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: u64) -> Result<()> {
+        if len >= self.len {
+            return Ok(());
+        }
+        let end = if len == 0 {
+            0
+        } else {
+            self.offset_of(len - 1) + R::SIZE as u64
+        };
+        self.buf.set_len(end)?;
+        self.len = len;
+        Ok(())
+    }
+    /// Flush buffer.
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.buf.flush()
+    }
+    /// Gets a mutable reference to the buffer.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut RaBuf<T> {
+        &mut self.buf
+    }
+    /// The offset of the record at `idx` in the file.
+    #[inline]
+    pub fn offset_of(&self, idx: u64) -> u64 {
+        match self.per_chunk {
+            None => idx * R::SIZE as u64,
+            Some(n) => (idx / n) * self.buf.chunk_size as u64 + (idx % n) * R::SIZE as u64,
+        }
+    }
+    #[inline]
+    fn check_index(&self, idx: u64) -> Result<()> {
+        if idx >= self.len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("record index {} is out of range {}", idx, self.len),
+            ));
+        }
+        Ok(())
+    }
+    fn write_at(&mut self, idx: u64, record: &R) -> Result<()> {
+        let offset = self.offset_of(idx);
+        self.buf.seek(SeekFrom::Start(offset))?;
+        record.encode(&mut self.buf)?;
+        let written = self.buf.pos.wrapping_sub(offset);
+        if written != R::SIZE as u64 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("record encoded {} bytes, not SIZE {}", written, R::SIZE),
+            ));
+        }
+        Ok(())
+    }
+}
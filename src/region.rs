@@ -0,0 +1,336 @@
+/*!
+Variable-size region allocator inside a file on top of [`RaBuf`].
+
+[`RegionFile`] allocates and frees the regions of the file, and keeps the
+free lists in the file, so they survive reopening.
+
+The layout of the file:
+- the header: a magic and the heads of the free lists of the size classes.
+- the blocks: each block has the size tag at the start and the end.
+  The region returned by `alloc()` is the bytes between the tags.
+  A free block holds the links of the free list in the region.
+
+The size class `k` holds the free blocks with the size in `32 << k` and
+`32 << (k + 1)`. A freed block is coalesced with the adjacent free blocks
+at once, so two free blocks are never adjacent. `compact()` trims the free
+block at the end of the file with [`FileSetLen::set_len()`].
+
+# Examples
+
+```rust
+use rabuf::{BufFile, RegionFile};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open("target/tmp/doc_test_region").unwrap();
+let bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+let mut rf = RegionFile::new(bf).unwrap();
+let a = rf.alloc(100).unwrap();
+let b = rf.alloc(10).unwrap();
+rf.get_mut().seek(SeekFrom::Start(b)).unwrap();
+rf.get_mut().write_all(b"0123456789").unwrap();
+rf.free(a).unwrap();
+// the freed block is reused
+assert_eq!(rf.alloc(50).unwrap(), a);
+let b = rf.realloc(b, 200).unwrap();
+let mut buf = [0u8; 10];
+rf.get_mut().seek(SeekFrom::Start(b)).unwrap();
+rf.get_mut().read_exact(&mut buf).unwrap();
+assert_eq!(&buf, b"0123456789");
+```
+*/
+use super::{FileSetLen, RaBuf, SmallRead, SmallWrite};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+const MAGIC: &[u8; 8] = b"RABUFRGN";
+/// The number of the size classes.
+const NUM_CLASSES: usize = 24;
+/// The alignment of the blocks.
+const ALIGN: u64 = 16;
+/// The magic and the heads of the free lists, rounded up to `ALIGN`.
+const HEADER_SIZE: u64 = (8 + 8 * NUM_CLASSES as u64 + ALIGN - 1) & !(ALIGN - 1);
+/// The size of the size tag.
+const TAG_SIZE: u64 = 8;
+/// The minimum block size: two tags and the links of the free list.
+const MIN_BLOCK: u64 = 32;
+/// The flag of the size tag, set when the block is allocated.
+const USED: u64 = 1;
+
+/// Variable-size region allocator.
+#[derive(Debug)]
+pub struct RegionFile<T: Seek + Read + Write + FileSetLen> {
+    buf: RaBuf<T>,
+}
+
+impl<T: Seek + Read + Write + FileSetLen> RegionFile<T> {
+    /// Creates a new RegionFile on the buffer.
+    /// An empty file is initialized with the header.
+    pub fn new(mut buf: RaBuf<T>) -> Result<RegionFile<T>> {
+        if buf.end == 0 {
+            buf.seek(SeekFrom::Start(0))?;
+            buf.write_all(MAGIC)?;
+            buf.write_all(&[0u8; (HEADER_SIZE - 8) as usize])?;
+        } else {
+            let mut magic = [0u8; 8];
+            if buf.end >= HEADER_SIZE && (buf.end - HEADER_SIZE) % ALIGN == 0 {
+                buf.seek(SeekFrom::Start(0))?;
+                buf.read_exact(&mut magic)?;
+            }
+            if &magic != MAGIC {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("rabuf \"{}\": not a region file", buf.name),
+                ));
+            }
+        }
+        Ok(Self { buf })
+    }
+    /// Allocates a region of `size` bytes, and returns the offset of it.
+    ///
+    /// A free block that fits is taken from the free lists, and the rest
+    /// of it is freed. If there is none, the file is extended.
+    pub fn alloc(&mut self, size: u64) -> Result<u64> {
+        let need = block_size(size)?;
+        if let Some((blk, bsize)) = self.find_free_(need)? {
+            self.remove_free_(blk, bsize)?;
+            self.split_(blk, bsize, need)?;
+            return Ok(blk + TAG_SIZE);
+        }
+        let blk = match self.last_free_()? {
+            Some((blk, bsize)) => {
+                self.remove_free_(blk, bsize)?;
+                blk
+            }
+            None => self.buf.end,
+        };
+        self.buf.set_len(blk + need)?;
+        self.set_tags_(blk, need, true)?;
+        Ok(blk + TAG_SIZE)
+    }
+    /// Frees the region at `offset`, and coalesces it with the adjacent free blocks.
+    pub fn free(&mut self, offset: u64) -> Result<()> {
+        let (blk, bsize) = self.used_block_(offset)?;
+        self.release_(blk, bsize)
+    }
+    /// Resizes the region at `offset` to `size` bytes, and returns the new offset.
+    ///
+    /// The region is shrunk or grown in place if possible. Otherwise the
+    /// contents are moved to a new region, and the old one is freed.
+    pub fn realloc(&mut self, offset: u64, size: u64) -> Result<u64> {
+        let (blk, bsize) = self.used_block_(offset)?;
+        let need = block_size(size)?;
+        if need <= bsize {
+            self.split_(blk, bsize, need)?;
+            return Ok(offset);
+        }
+        let next = blk + bsize;
+        if next == self.buf.end {
+            self.buf.set_len(blk + need)?;
+            self.set_tags_(blk, need, true)?;
+            return Ok(offset);
+        }
+        let tag = self.read_u64_at_(next)?;
+        if tag & USED == 0 && bsize + tag >= need {
+            self.remove_free_(next, tag)?;
+            self.split_(blk, bsize + tag, need)?;
+            return Ok(offset);
+        }
+        let new_offset = self.alloc(size)?;
+        self.copy_(offset, new_offset, bsize - 2 * TAG_SIZE)?;
+        self.release_(blk, bsize)?;
+        Ok(new_offset)
+    }
+    /// The usable size in bytes of the region at `offset`.
+    /// It is `size` of `alloc()` rounded up.
+    pub fn capacity(&mut self, offset: u64) -> Result<u64> {
+        let (_, bsize) = self.used_block_(offset)?;
+        Ok(bsize - 2 * TAG_SIZE)
+    }
+    /// Trims the free block at the end of the file.
+    pub fn compact(&mut self) -> Result<()> {
+        if let Some((blk, bsize)) = self.last_free_()? {
+            self.remove_free_(blk, bsize)?;
+            self.buf.set_len(blk)?;
+        }
+        Ok(())
+    }
+    /// Flush buffer.
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.buf.flush()
+    }
+    /// Gets a mutable reference to the buffer.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut RaBuf<T> {
+        &mut self.buf
+    }
+    /// Returns the block and its size of the allocated region at `offset`.
+    fn used_block_(&mut self, offset: u64) -> Result<(u64, u64)> {
+        if offset < HEADER_SIZE + TAG_SIZE
+            || (offset - TAG_SIZE - HEADER_SIZE) % ALIGN != 0
+            || offset >= self.buf.end
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid region offset: {}", offset),
+            ));
+        }
+        let blk = offset - TAG_SIZE;
+        let tag = self.read_u64_at_(blk)?;
+        if tag & USED == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("region is not allocated: {}", offset),
+            ));
+        }
+        let bsize = tag & !USED;
+        if bsize < MIN_BLOCK || blk + bsize > self.buf.end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("broken block size at {}: {}", blk, bsize),
+            ));
+        }
+        Ok((blk, bsize))
+    }
+    /// Finds a free block of `need` bytes or more.
+    fn find_free_(&mut self, need: u64) -> Result<Option<(u64, u64)>> {
+        let class = class_of(need);
+        // the blocks of the same class may be smaller than `need`.
+        let mut blk = self.read_u64_at_(head_offset(class))?;
+        while blk != 0 {
+            let bsize = self.read_u64_at_(blk)?;
+            if bsize >= need {
+                return Ok(Some((blk, bsize)));
+            }
+            blk = self.read_u64_at_(blk + TAG_SIZE)?;
+        }
+        // the blocks of the larger classes are always large enough.
+        for k in (class + 1)..NUM_CLASSES {
+            let blk = self.read_u64_at_(head_offset(k))?;
+            if blk != 0 {
+                let bsize = self.read_u64_at_(blk)?;
+                return Ok(Some((blk, bsize)));
+            }
+        }
+        Ok(None)
+    }
+    /// Returns the free block at the end of the file.
+    fn last_free_(&mut self) -> Result<Option<(u64, u64)>> {
+        if self.buf.end <= HEADER_SIZE {
+            return Ok(None);
+        }
+        let tag = self.read_u64_at_(self.buf.end - TAG_SIZE)?;
+        if tag & USED != 0 {
+            return Ok(None);
+        }
+        Ok(Some((self.buf.end - tag, tag)))
+    }
+    /// Marks `need` bytes of the block as allocated, and frees the rest.
+    fn split_(&mut self, blk: u64, bsize: u64, need: u64) -> Result<()> {
+        if bsize - need >= MIN_BLOCK {
+            self.set_tags_(blk, need, true)?;
+            self.release_(blk + need, bsize - need)
+        } else {
+            self.set_tags_(blk, bsize, true)
+        }
+    }
+    /// Coalesces the block with the adjacent free blocks, and links it to the free list.
+    fn release_(&mut self, mut blk: u64, mut bsize: u64) -> Result<()> {
+        let next = blk + bsize;
+        if next < self.buf.end {
+            let tag = self.read_u64_at_(next)?;
+            if tag & USED == 0 {
+                self.remove_free_(next, tag)?;
+                bsize += tag;
+            }
+        }
+        if blk > HEADER_SIZE {
+            let tag = self.read_u64_at_(blk - TAG_SIZE)?;
+            if tag & USED == 0 {
+                self.remove_free_(blk - tag, tag)?;
+                blk -= tag;
+                bsize += tag;
+            }
+        }
+        self.set_tags_(blk, bsize, false)?;
+        self.insert_free_(blk, bsize)
+    }
+    fn insert_free_(&mut self, blk: u64, bsize: u64) -> Result<()> {
+        let head_off = head_offset(class_of(bsize));
+        let head = self.read_u64_at_(head_off)?;
+        self.write_u64_at_(blk + TAG_SIZE, head)?;
+        self.write_u64_at_(blk + 2 * TAG_SIZE, 0)?;
+        if head != 0 {
+            self.write_u64_at_(head + 2 * TAG_SIZE, blk)?;
+        }
+        self.write_u64_at_(head_off, blk)
+    }
+    fn remove_free_(&mut self, blk: u64, bsize: u64) -> Result<()> {
+        let next = self.read_u64_at_(blk + TAG_SIZE)?;
+        let prev = self.read_u64_at_(blk + 2 * TAG_SIZE)?;
+        if prev == 0 {
+            self.write_u64_at_(head_offset(class_of(bsize)), next)?;
+        } else {
+            self.write_u64_at_(prev + TAG_SIZE, next)?;
+        }
+        if next != 0 {
+            self.write_u64_at_(next + 2 * TAG_SIZE, prev)?;
+        }
+        Ok(())
+    }
+    fn set_tags_(&mut self, blk: u64, bsize: u64, used: bool) -> Result<()> {
+        let tag = if used { bsize | USED } else { bsize };
+        self.write_u64_at_(blk, tag)?;
+        self.write_u64_at_(blk + bsize - TAG_SIZE, tag)
+    }
+    /// Copies `len` bytes of a region to another.
+    fn copy_(&mut self, src: u64, dst: u64, len: u64) -> Result<()> {
+        let mut buf = vec![0u8; (len as usize).min(self.buf.chunk_size)];
+        let mut done = 0;
+        while done < len {
+            let n = (buf.len() as u64).min(len - done) as usize;
+            self.buf.seek(SeekFrom::Start(src + done))?;
+            self.buf.read_exact(&mut buf[..n])?;
+            self.buf.seek(SeekFrom::Start(dst + done))?;
+            self.buf.write_all(&buf[..n])?;
+            done += n as u64;
+        }
+        Ok(())
+    }
+    #[inline]
+    fn read_u64_at_(&mut self, offset: u64) -> Result<u64> {
+        self.buf.seek(SeekFrom::Start(offset))?;
+        self.buf.read_u64_le()
+    }
+    #[inline]
+    fn write_u64_at_(&mut self, offset: u64, val: u64) -> Result<()> {
+        self.buf.seek(SeekFrom::Start(offset))?;
+        self.buf.write_u64_le(val)
+    }
+}
+
+/// The block size for a region of `size` bytes.
+fn block_size(size: u64) -> Result<u64> {
+    match size.checked_add(2 * TAG_SIZE + ALIGN - 1) {
+        Some(x) => Ok((x & !(ALIGN - 1)).max(MIN_BLOCK)),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("too large region size: {}", size),
+        )),
+    }
+}
+
+/// The size class of a block.
+#[inline]
+fn class_of(bsize: u64) -> usize {
+    let k = 63 - (bsize / MIN_BLOCK).leading_zeros() as usize;
+    k.min(NUM_CLASSES - 1)
+}
+
+/// The offset of the head of the free list in the header.
+#[inline]
+fn head_offset(class: usize) -> u64 {
+    8 + 8 * class as u64
+}
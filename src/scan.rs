@@ -0,0 +1,247 @@
+/*!
+Chunk-granular scan over a range.
+
+[`RaChunks`] yields the data of a range chunk by chunk, clamped to the end.
+A cached chunk is yielded in place, with its dirty data. A chunk that is
+not cached is read into a scan buffer of the iterator, and does not go into
+the chunk cache, so a full-file scan does not evict the hot working set.
+
+The yielded slice borrows the iterator, so it is a lending iterator with
+[`RaChunks::next_chunk()`] instead of `Iterator`.
+
+[`RaBuf::find()`], [`RaBuf::rfind()`] and [`RaBuf::find_byte()`] search in
+the same way, and a match may span the chunks.
+
+# Examples
+
+```rust
+use rabuf::BufFile;
+use std::io::Write;
+
+std::fs::create_dir_all("target/tmp").unwrap();
+let path = "target/tmp/doc_test_scan";
+let f = std::fs::OpenOptions::new()
+    .create(true).truncate(true).read(true).write(true)
+    .open(path).unwrap();
+let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+bf.write_all(&[1u8; 10000]).unwrap();
+let mut sum = 0u64;
+let mut iter = bf.chunks(..);
+while let Some(r) = iter.next_chunk() {
+    let (_offset, data) = r.unwrap();
+    sum += data.iter().map(|&x| x as u64).sum::<u64>();
+}
+assert_eq!(sum, 10000);
+```
+*/
+use super::{read_load, RaBuf, RaBufError, RaBufOp};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+use std::ops::{Bound, RangeBounds};
+
+impl<T: Seek + Read + Write> RaBuf<T> {
+    /// Returns a scan over the chunks of `range`, clamped to the end.
+    ///
+    /// `..` scans the whole file. The current position is not changed.
+    pub fn chunks<R: RangeBounds<u64>>(&mut self, range: R) -> RaChunks<'_, T> {
+        let (st, ed) = self.scan_range_(range);
+        RaChunks {
+            buf: self,
+            curr: st,
+            ed,
+            scan: Vec::new(),
+        }
+    }
+    /// Returns the offset of the first `pattern` in `range`, or None.
+    ///
+    /// The match may span the chunks. The current position is not changed.
+    pub fn find<R: RangeBounds<u64>>(&mut self, pattern: &[u8], range: R) -> Result<Option<u64>> {
+        check_pattern_(pattern)?;
+        let (st, ed) = self.scan_range_(range);
+        let k = pattern.len() - 1;
+        let chunk_size = self.chunk_size as u64;
+        let mut scan = Vec::new();
+        // the last `k` bytes before `curr`
+        let mut carry: Vec<u8> = Vec::new();
+        let mut curr = st;
+        while curr < ed {
+            let chunk_off = curr & self.chunk_mask;
+            let s = (curr - chunk_off) as usize;
+            let n = (chunk_size - s as u64).min(ed - curr) as usize;
+            let data = &self.scan_chunk_(chunk_off, &mut scan)?[s..(s + n)];
+            if !carry.is_empty() {
+                // a match here starts in the carry
+                let mut b = carry.clone();
+                b.extend_from_slice(&data[..n.min(k)]);
+                if let Some(i) = find_in_(&b, pattern) {
+                    return Ok(Some(curr - carry.len() as u64 + i as u64));
+                }
+            }
+            if let Some(i) = find_in_(data, pattern) {
+                return Ok(Some(curr + i as u64));
+            }
+            carry.extend_from_slice(&data[(n - n.min(k))..]);
+            if carry.len() > k {
+                carry.drain(..(carry.len() - k));
+            }
+            curr += n as u64;
+        }
+        Ok(None)
+    }
+    /// Returns the offset of the last `pattern` in `range`, or None.
+    ///
+    /// The match may span the chunks. The current position is not changed.
+    pub fn rfind<R: RangeBounds<u64>>(&mut self, pattern: &[u8], range: R) -> Result<Option<u64>> {
+        check_pattern_(pattern)?;
+        let (st, ed) = self.scan_range_(range);
+        let k = pattern.len() - 1;
+        let mut scan = Vec::new();
+        // the first `k` bytes after `curr`
+        let mut carry: Vec<u8> = Vec::new();
+        let mut curr = ed;
+        while curr > st {
+            let chunk_off = (curr - 1) & self.chunk_mask;
+            let s = chunk_off.max(st);
+            let n = (curr - s) as usize;
+            let s_in = (s - chunk_off) as usize;
+            let data = &self.scan_chunk_(chunk_off, &mut scan)?[s_in..(s_in + n)];
+            if !carry.is_empty() {
+                // a match here ends in the carry
+                let tail = n.min(k);
+                let mut b = data[(n - tail)..].to_vec();
+                b.extend_from_slice(&carry);
+                if let Some(i) = rfind_in_(&b, pattern) {
+                    return Ok(Some(curr - tail as u64 + i as u64));
+                }
+            }
+            if let Some(i) = rfind_in_(data, pattern) {
+                return Ok(Some(s + i as u64));
+            }
+            let head = n.min(k);
+            carry.splice(0..0, data[..head].iter().copied());
+            carry.truncate(k);
+            curr = s;
+        }
+        Ok(None)
+    }
+    /// Returns the offset of the first `byte` in `range`, or None.
+    ///
+    /// The current position is not changed.
+    pub fn find_byte<R: RangeBounds<u64>>(&mut self, byte: u8, range: R) -> Result<Option<u64>> {
+        let mut iter = self.chunks(range);
+        while let Some(r) = iter.next_chunk() {
+            let (offset, data) = r?;
+            if let Some(i) = data.iter().position(|&x| x == byte) {
+                return Ok(Some(offset + i as u64));
+            }
+        }
+        Ok(None)
+    }
+    /// Returns the start and the end of `range`, clamped to the end.
+    fn scan_range_<R: RangeBounds<u64>>(&self, range: R) -> (u64, u64) {
+        let st = match range.start_bound() {
+            Bound::Included(&x) => x,
+            Bound::Excluded(&x) => x.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let ed = match range.end_bound() {
+            Bound::Included(&x) => x.saturating_add(1),
+            Bound::Excluded(&x) => x,
+            Bound::Unbounded => u64::MAX,
+        };
+        (st, ed.min(self.end))
+    }
+    /// Returns the data of the chunk at `chunk_off`, the cached chunk, or else
+    /// `scan` that is read without caching it.
+    fn scan_chunk_<'a>(&'a mut self, chunk_off: u64, scan: &'a mut Vec<u8>) -> Result<&'a [u8]> {
+        self.check_poisoned_()?;
+        if let Some(idx) = self.map.get(&chunk_off) {
+            return Ok(&self.chunks[idx].data);
+        }
+        let chunk_size = self.chunk_size;
+        scan.clear();
+        scan.resize(chunk_size, 0u8);
+        let load_end = self.load_end_(chunk_off)?;
+        if chunk_off < load_end {
+            let n = (load_end - chunk_off).min(chunk_size as u64) as usize;
+            let short_ok = self.short_load_ok_(chunk_off);
+            let load_err =
+                |err| RaBufError::new(&self.name, RaBufOp::Load, chunk_off, chunk_size as u64, err);
+            self.file
+                .seek(SeekFrom::Start(chunk_off))
+                .map_err(load_err)?;
+            read_load(&mut self.file, &mut scan[..n], short_ok).map_err(load_err)?;
+        }
+        Ok(scan)
+    }
+}
+
+/// Returns an error if `pattern` is empty.
+fn check_pattern_(pattern: &[u8]) -> Result<()> {
+    if pattern.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "search pattern is empty",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the index of the first `pattern` in `hay`.
+fn find_in_(hay: &[u8], pattern: &[u8]) -> Option<usize> {
+    if hay.len() < pattern.len() {
+        return None;
+    }
+    if pattern.len() == 1 {
+        return hay.iter().position(|&x| x == pattern[0]);
+    }
+    hay.windows(pattern.len()).position(|w| w == pattern)
+}
+
+/// Returns the index of the last `pattern` in `hay`.
+fn rfind_in_(hay: &[u8], pattern: &[u8]) -> Option<usize> {
+    if hay.len() < pattern.len() {
+        return None;
+    }
+    if pattern.len() == 1 {
+        return hay.iter().rposition(|&x| x == pattern[0]);
+    }
+    hay.windows(pattern.len()).rposition(|w| w == pattern)
+}
+
+/// Lending iterator over the chunks of a range.
+#[derive(Debug)]
+pub struct RaChunks<'a, T: Seek + Read + Write> {
+    buf: &'a mut RaBuf<T>,
+    /// The offset of the next data.
+    curr: u64,
+    /// The end of the range.
+    ed: u64,
+    /// The data of a chunk that is not cached.
+    scan: Vec<u8>,
+}
+
+impl<T: Seek + Read + Write> RaChunks<'_, T> {
+    /// Returns the offset and the data of the next chunk, or None at the end of the range.
+    ///
+    /// The first data starts at the start of the range, and the others at
+    /// a chunk boundary.
+    pub fn next_chunk(&mut self) -> Option<Result<(u64, &[u8])>> {
+        if self.curr >= self.ed {
+            return None;
+        }
+        let curr = self.curr;
+        let chunk_off = curr & self.buf.chunk_mask;
+        let st = (curr - chunk_off) as usize;
+        let n = (self.buf.chunk_size - st).min((self.ed - curr) as usize);
+        match self.buf.scan_chunk_(chunk_off, &mut self.scan) {
+            Ok(data) => {
+                self.curr += n as u64;
+                Some(Ok((curr, &data[st..(st + n)])))
+            }
+            Err(err) => {
+                self.curr = self.ed;
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+/*!
+Segmented file that spans several files of a fixed size.
+
+[`SegmentedFile`] presents the files `base.000`, `base.001`, ... as one
+address space. The segment `i` holds the bytes from `i * segment_size`,
+and all the segments but the last are full. A write past the end creates
+the new segments, and `set_len()` removes or creates the segments and
+truncates the last one. `sync_all()`, `sync_data()`, `sync_range()` and
+`advise()` apply to every segment in the range.
+
+[`SegmentedBufFile`] is [`RaBuf`] on it. The segment size is a multiple of
+the chunk size, so a chunk never straddles a segment.
+
+# Examples
+
+```rust
+use rabuf::{SegmentedBufFile, SegmentedFile};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+std::fs::create_dir_all("target/tmp").unwrap();
+# for i in 0..3 { let _ = std::fs::remove_file(format!("target/tmp/doc_test_segment.{:03}", i)); }
+let sf = SegmentedFile::open("target/tmp/doc_test_segment", 8192).unwrap();
+let mut bf = SegmentedBufFile::with_segments("tes", sf, 4096, 4).unwrap();
+bf.write_all(&[0x5a; 20000]).unwrap();
+bf.flush().unwrap();
+assert!(std::path::Path::new("target/tmp/doc_test_segment.002").exists());
+bf.seek(SeekFrom::Start(8190)).unwrap();
+let mut buf = [0u8; 4];
+bf.read_exact(&mut buf).unwrap();
+assert_eq!(buf, [0x5a; 4]);
+```
+*/
+use super::{
+    seek_pos, Advice, FileAdvise, FileCopyRange, FilePunchHole, FileSeekData, FileSetLen, FileSync,
+    RaBuf,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Buffered segmented file for random access.
+pub type SegmentedBufFile = RaBuf<SegmentedFile>;
+
+impl SegmentedBufFile {
+    /// Creates a new SegmentedBufFile with the specified number of chunks.
+    /// chunk_size is MUST power of 2, and the segment size MUST be a multiple of it.
+    pub fn with_segments(
+        name: &str,
+        file: SegmentedFile,
+        chunk_size: u32,
+        max_num_chunks: u16,
+    ) -> Result<SegmentedBufFile> {
+        let segment_size = file.segment_size();
+        if segment_size % chunk_size as u64 != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "segment size {} is not a multiple of chunk size {}",
+                    segment_size, chunk_size
+                ),
+            ));
+        }
+        Self::with_capacity(name, file, chunk_size, max_num_chunks)
+    }
+}
+
+/// Segmented file of fixed size segments.
+#[derive(Debug)]
+pub struct SegmentedFile {
+    /// The path of the segments without the suffix.
+    base: PathBuf,
+    /// The size of a segment.
+    segment_size: u64,
+    /// The segment files, at least one.
+    segments: Vec<File>,
+    /// The current position.
+    pos: u64,
+    /// The logical length.
+    len: u64,
+    /// Set when a segment is created or removed, until the directory is synced.
+    dir_dirty: bool,
+}
+
+impl SegmentedFile {
+    /// Opens the segments `base.000`, `base.001`, ..., up to the first missing one.
+    /// If there is none, `base.000` is created.
+    /// All the segments but the last MUST be of `segment_size`.
+    pub fn open<P: AsRef<Path>>(base: P, segment_size: u64) -> Result<SegmentedFile> {
+        if segment_size == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "segment size is zero",
+            ));
+        }
+        let mut sf = Self {
+            base: base.as_ref().to_path_buf(),
+            segment_size,
+            segments: Vec::new(),
+            pos: 0,
+            len: 0,
+            dir_dirty: false,
+        };
+        loop {
+            let path = sf.segment_path(sf.segments.len());
+            if !path.exists() {
+                break;
+            }
+            let file = OpenOptions::new().read(true).write(true).open(&path)?;
+            let file_len = file.metadata()?.len();
+            // the previous segments are full.
+            let full = sf.len == sf.segments.len() as u64 * segment_size;
+            if file_len > segment_size || !full {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("broken segment size {}: {:?}", file_len, path),
+                ));
+            }
+            sf.len = sf.segments.len() as u64 * segment_size + file_len;
+            sf.segments.push(file);
+        }
+        if sf.segments.is_empty() {
+            sf.create_segment_()?;
+        }
+        Ok(sf)
+    }
+    /// The size of a segment.
+    #[inline]
+    pub fn segment_size(&self) -> u64 {
+        self.segment_size
+    }
+    /// The number of the segments.
+    #[inline]
+    pub fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+    /// The path of the segment `idx`.
+    pub fn segment_path(&self, idx: usize) -> PathBuf {
+        let mut path = self.base.clone().into_os_string();
+        path.push(format!(".{:03}", idx));
+        PathBuf::from(path)
+    }
+    /// Creates the next segment.
+    fn create_segment_(&mut self) -> Result<()> {
+        let path = self.segment_path(self.segments.len());
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(path)?;
+        self.segments.push(file);
+        self.dir_dirty = true;
+        Ok(())
+    }
+    /// Creates the segments up to `idx`, filling the previous ones to full.
+    fn ensure_segment_(&mut self, idx: usize) -> Result<()> {
+        while self.segments.len() <= idx {
+            if let Some(last) = self.segments.last_mut() {
+                last.set_len(self.segment_size)?;
+            }
+            self.len = self.segments.len() as u64 * self.segment_size;
+            self.create_segment_()?;
+        }
+        Ok(())
+    }
+    /// Calls `f` with the segment, the offset and the length in it, for each segment in the range.
+    fn for_each_segment_<F>(&mut self, offset: u64, len: u64, mut f: F) -> Result<()>
+    where
+        F: FnMut(&mut File, u64, u64) -> Result<()>,
+    {
+        let ed = offset.saturating_add(len).min(self.len);
+        let mut curr = offset;
+        while curr < ed {
+            let idx = (curr / self.segment_size) as usize;
+            let seg_off = curr % self.segment_size;
+            let n = (self.segment_size - seg_off).min(ed - curr);
+            f(&mut self.segments[idx], seg_off, n)?;
+            curr += n;
+        }
+        Ok(())
+    }
+    /// Syncs the directory of the segments, after a segment is created or removed.
+    fn sync_dir_(&mut self) -> Result<()> {
+        if !self.dir_dirty {
+            return Ok(());
+        }
+        #[cfg(unix)]
+        {
+            let dir = match self.base.parent() {
+                Some(x) if !x.as_os_str().is_empty() => x,
+                _ => Path::new("."),
+            };
+            File::open(dir)?.sync_all()?;
+        }
+        self.dir_dirty = false;
+        Ok(())
+    }
+}
+
+impl Read for SegmentedFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        let idx = (self.pos / self.segment_size) as usize;
+        let seg_off = self.pos % self.segment_size;
+        let n = (self.segment_size - seg_off)
+            .min(self.len - self.pos)
+            .min(buf.len() as u64) as usize;
+        let file = &mut self.segments[idx];
+        file.seek(SeekFrom::Start(seg_off))?;
+        let n = file.read(&mut buf[..n])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Write for SegmentedFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let idx = (self.pos / self.segment_size) as usize;
+        let seg_off = self.pos % self.segment_size;
+        self.ensure_segment_(idx)?;
+        let n = (self.segment_size - seg_off).min(buf.len() as u64) as usize;
+        let file = &mut self.segments[idx];
+        file.seek(SeekFrom::Start(seg_off))?;
+        let n = file.write(&buf[..n])?;
+        self.pos += n as u64;
+        if self.len < self.pos {
+            self.len = self.pos;
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<()> {
+        for file in self.segments.iter_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Seek for SegmentedFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.pos = seek_pos(self.pos, self.len, pos)?;
+        Ok(self.pos)
+    }
+}
+
+impl FileSetLen for SegmentedFile {
+    /// Removes or creates the segments, and truncates or extends the last one.
+    fn set_len(&mut self, size: u64) -> Result<()> {
+        let num = ((size + self.segment_size - 1) / self.segment_size).max(1) as usize;
+        if num > self.segments.len() {
+            self.ensure_segment_(num - 1)?;
+        }
+        while self.segments.len() > num {
+            // pops the segment only after it is removed, so a failure leaves it usable.
+            let idx = self.segments.len() - 1;
+            std::fs::remove_file(self.segment_path(idx))?;
+            self.dir_dirty = true;
+            drop(self.segments.pop());
+            self.len = self.len.min(idx as u64 * self.segment_size);
+        }
+        self.segments[num - 1].set_len(size - (num - 1) as u64 * self.segment_size)?;
+        self.len = size;
+        Ok(())
+    }
+}
+
+impl FileSync for SegmentedFile {
+    /// Syncs all the segments, and the directory if a segment was created or removed.
+    fn sync_all(&mut self) -> Result<()> {
+        for file in self.segments.iter_mut() {
+            file.sync_all()?;
+        }
+        self.sync_dir_()
+    }
+    fn sync_data(&mut self) -> Result<()> {
+        for file in self.segments.iter_mut() {
+            file.sync_data()?;
+        }
+        self.sync_dir_()
+    }
+    fn sync_range(&mut self, offset: u64, len: u64) -> Result<()> {
+        // zero means up to the end.
+        let len = if len == 0 { u64::MAX } else { len };
+        self.for_each_segment_(offset, len, |file, off, n| file.sync_range(off, n))?;
+        self.sync_dir_()
+    }
+}
+
+impl FileCopyRange for SegmentedFile {}
+
+impl FilePunchHole for SegmentedFile {
+    fn punch_hole(&mut self, offset: u64, len: u64) -> Result<bool> {
+        let mut punched = true;
+        self.for_each_segment_(offset, len, |file, off, n| {
+            punched &= file.punch_hole(off, n)?;
+            Ok(())
+        })?;
+        Ok(punched)
+    }
+}
+
+impl FileSeekData for SegmentedFile {
+    fn next_data(&mut self, offset: u64) -> Result<Option<u64>> {
+        let mut idx = (offset / self.segment_size) as usize;
+        let mut seg_off = offset % self.segment_size;
+        while idx < self.segments.len() {
+            if let Some(x) = self.segments[idx].next_data(seg_off)? {
+                return Ok(Some(idx as u64 * self.segment_size + x));
+            }
+            idx += 1;
+            seg_off = 0;
+        }
+        Ok(None)
+    }
+    fn next_hole(&mut self, offset: u64) -> Result<Option<u64>> {
+        if offset >= self.len {
+            return Ok(None);
+        }
+        let mut idx = (offset / self.segment_size) as usize;
+        let mut seg_off = offset % self.segment_size;
+        loop {
+            let base = idx as u64 * self.segment_size;
+            let hole = self.segments[idx].next_hole(seg_off)?;
+            match hole {
+                // the end of a full segment is not a hole.
+                Some(x) if x >= self.segment_size && idx + 1 < self.segments.len() => {}
+                Some(x) => return Ok(Some(base + x)),
+                None if idx + 1 < self.segments.len() => {}
+                None => return Ok(Some(self.len)),
+            }
+            idx += 1;
+            seg_off = 0;
+        }
+    }
+}
+
+impl FileAdvise for SegmentedFile {
+    fn advise(&mut self, offset: u64, len: u64, advice: Advice) -> Result<()> {
+        // zero means up to the end.
+        let len = if len == 0 { u64::MAX } else { len };
+        self.for_each_segment_(offset, len, |file, off, n| file.advise(off, n, advice))
+    }
+}
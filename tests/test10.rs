@@ -0,0 +1,117 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test10 {
+    use function_name::named;
+    use rabuf::{BufFile, SmallRead, SmallWrite};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[named]
+    #[test]
+    fn test_bypass_write_and_read() {
+        let data = test_data(100_000);
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 1024, 4).unwrap();
+        bf.set_bypass_threshold(8 * 1024);
+        bf.seek(SeekFrom::Start(100)).unwrap();
+        bf.write_all(&data).unwrap();
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 100_100);
+        //
+        bf.seek(SeekFrom::Start(100)).unwrap();
+        let mut br = vec![0u8; data.len()];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(br, data);
+        //
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut br = vec![0u8; 100];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(br, vec![0u8; 100]);
+    }
+
+    #[named]
+    #[test]
+    fn test_bypass_read_sees_dirty_chunks() {
+        let data = test_data(20_000);
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 1024, 4).unwrap();
+        bf.write_all(&data).unwrap();
+        bf.flush().unwrap();
+        // dirty cached chunk in the middle of the range
+        bf.seek(SeekFrom::Start(5 * 1024 + 10)).unwrap();
+        bf.write_u32_le(0xdeadbeef).unwrap();
+        //
+        bf.set_bypass_threshold(4 * 1024);
+        bf.rewind().unwrap();
+        let mut br = vec![0u8; data.len()];
+        bf.read_exact(&mut br).unwrap();
+        let mut expected = data.clone();
+        expected[(5 * 1024 + 10)..(5 * 1024 + 14)].copy_from_slice(&0xdeadbeef_u32.to_le_bytes());
+        assert_eq!(br, expected);
+    }
+
+    #[named]
+    #[test]
+    fn test_bypass_write_updates_cached_chunks() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 1024, 4).unwrap();
+        bf.write_all(&[0x11; 8 * 1024]).unwrap();
+        // keep chunk at 2048 in the cache
+        bf.seek(SeekFrom::Start(2048)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x11);
+        //
+        bf.set_bypass_threshold(4 * 1024);
+        bf.rewind().unwrap();
+        bf.write_all(&[0x22; 8 * 1024]).unwrap();
+        //
+        bf.seek(SeekFrom::Start(2048)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x22);
+        bf.rewind().unwrap();
+        let mut br = vec![0u8; 8 * 1024];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(br, vec![0x22; 8 * 1024]);
+    }
+
+    #[named]
+    #[test]
+    fn test_bypass_close_and_open() {
+        let data = test_data(50_000);
+        {
+            let f = open_test_file!(function_name!());
+            let mut bf = BufFile::with_capacity("tes", f, 4096, 2).unwrap();
+            bf.set_bypass_threshold(16 * 1024);
+            bf.write_all(&data[..30]).unwrap();
+            bf.write_all(&data[30..]).unwrap();
+        }
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), data);
+    }
+
+    #[named]
+    #[test]
+    fn test_bypass_read_truncated_behind() {
+        let data = test_data(8 * 1024);
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 1024, 4).unwrap();
+        bf.write_all(&data).unwrap();
+        bf.flush().unwrap();
+        bf.clear().unwrap();
+        // truncated by the other
+        let path = concat!(base_dir!(), "/", function_name!());
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_len(3 * 1024)
+            .unwrap();
+        bf.set_bypass_threshold(4 * 1024);
+        bf.seek(SeekFrom::Start(2 * 1024)).unwrap();
+        let mut br = vec![0u8; 4 * 1024];
+        let err = bf.read_exact(&mut br).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,120 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test11 {
+    use function_name::named;
+    use rabuf::{BufFile, SmallRead};
+    use std::io::{ErrorKind, Seek, SeekFrom, Write};
+
+    // overwrites the file behind the buffer, so that a resident chunk
+    // keeps the old data and a reloaded chunk shows the new data.
+    fn overwrite(f: &mut std::fs::File, offset: u64, val: u8) {
+        f.seek(SeekFrom::Start(offset)).unwrap();
+        f.write_all(&[val]).unwrap();
+    }
+
+    fn churn(bf: &mut BufFile) {
+        for i in 0..64 {
+            bf.seek(SeekFrom::Start(i * 16)).unwrap();
+            let _ = bf.read_u8().unwrap();
+        }
+        bf.clear().unwrap();
+    }
+
+    #[named]
+    #[test]
+    fn test_pin_range_stays_resident() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        bf.flush().unwrap();
+        //
+        bf.pin_range(500, 20).unwrap();
+        overwrite(&mut f2, 500, 0x22);
+        overwrite(&mut f2, 515, 0x22);
+        overwrite(&mut f2, 700, 0x22);
+        churn(&mut bf);
+        //
+        bf.seek(SeekFrom::Start(500)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x11);
+        bf.seek(SeekFrom::Start(515)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x11);
+        bf.seek(SeekFrom::Start(700)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x22);
+        //
+        bf.unpin_range(500, 20);
+        churn(&mut bf);
+        bf.seek(SeekFrom::Start(500)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x22);
+    }
+
+    #[named]
+    #[test]
+    fn test_pin_range_counts() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        bf.flush().unwrap();
+        //
+        bf.pin_range(320, 1).unwrap();
+        bf.pin_range(320, 1).unwrap();
+        bf.unpin_range(320, 1);
+        overwrite(&mut f2, 320, 0x22);
+        churn(&mut bf);
+        bf.seek(SeekFrom::Start(320)).unwrap();
+        assert_eq!(bf.read_u8().unwrap(), 0x11);
+    }
+
+    #[named]
+    #[test]
+    fn test_pin_range_dirty_is_kept() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        bf.pin_range(64, 16).unwrap();
+        bf.seek(SeekFrom::Start(70)).unwrap();
+        bf.write_all(&[0x33; 4]).unwrap();
+        churn(&mut bf);
+        bf.seek(SeekFrom::Start(70)).unwrap();
+        assert_eq!(bf.read_u32_le().unwrap(), 0x33333333);
+    }
+
+    #[named]
+    #[test]
+    fn test_pin_range_over_capacity() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        assert!(bf.pin_range(0, 16 * 8).is_err());
+        bf.pin_range(0, 16 * 2).unwrap();
+        assert!(bf.pin_range(512, 16 * 2).is_err());
+        // the failed pin does not change anything
+        bf.unpin_range(0, 16 * 2);
+        bf.pin_range(512, 16 * 2).unwrap();
+    }
+
+    #[named]
+    #[test]
+    fn test_pin_range_huge() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 8).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        // the capacity error without listing the chunks
+        let err = bf.pin_range(0, 1 << 40).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = bf.pin_range(u64::MAX - 10, 100).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        bf.unpin_range(u64::MAX - 10, 100);
+        bf.unpin_range(0, 1 << 40);
+        //
+        let f = open_test_file!(concat!(function_name!(), "_1"));
+        let mut bf = BufFile::with_capacity("tes", f, 16, 1).unwrap();
+        let err = bf.pin_range(16, 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = bf.pin_range(0, 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,127 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test12 {
+    use function_name::named;
+    use rabuf::{Advice, BufFile, FileAdvise, SmallRead};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    // overwrites the file behind the buffer, so that a resident chunk
+    // keeps the old data and a reloaded chunk shows the new data.
+    fn overwrite(f: &mut std::fs::File, offset: u64, val: u8) {
+        f.seek(SeekFrom::Start(offset)).unwrap();
+        f.write_all(&[val]).unwrap();
+    }
+
+    fn read_u8_at(bf: &mut BufFile, offset: u64) -> u8 {
+        bf.seek(SeekFrom::Start(offset)).unwrap();
+        bf.read_u8().unwrap()
+    }
+
+    #[named]
+    #[test]
+    fn test_advise_will_need() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 8).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        bf.clear().unwrap();
+        //
+        bf.advise(256, 48, Advice::WillNeed).unwrap();
+        overwrite(&mut f2, 256, 0x22);
+        overwrite(&mut f2, 300, 0x22);
+        overwrite(&mut f2, 320, 0x22);
+        assert_eq!(read_u8_at(&mut bf, 256), 0x11);
+        assert_eq!(read_u8_at(&mut bf, 300), 0x11);
+        assert_eq!(read_u8_at(&mut bf, 320), 0x22);
+    }
+
+    #[named]
+    #[test]
+    fn test_advise_dont_need() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 8).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        bf.seek(SeekFrom::Start(500)).unwrap();
+        bf.write_all(&[0x33; 4]).unwrap();
+        //
+        bf.advise(496, 16, Advice::DontNeed).unwrap();
+        // the dirty data was written back
+        let mut buf = [0u8; 4];
+        f2.seek(SeekFrom::Start(500)).unwrap();
+        f2.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x33; 4]);
+        // and the chunk was dropped
+        overwrite(&mut f2, 504, 0x22);
+        assert_eq!(read_u8_at(&mut bf, 504), 0x22);
+    }
+
+    #[named]
+    #[test]
+    fn test_advise_sequential_keeps_hot_chunks() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        let data: Vec<u8> = (0..2048).map(|i| (i % 251) as u8).collect();
+        bf.write_all(&data).unwrap();
+        bf.clear().unwrap();
+        //
+        let hot = read_u8_at(&mut bf, 1600);
+        overwrite(&mut f2, 1600, hot.wrapping_add(1));
+        //
+        bf.advise(0, 1024, Advice::Sequential).unwrap();
+        bf.rewind().unwrap();
+        let mut br = vec![0u8; 1024];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(&br, &data[..1024]);
+        assert_eq!(read_u8_at(&mut bf, 1600), hot);
+    }
+
+    #[named]
+    #[test]
+    fn test_advise_no_reuse_and_normal() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x11; 2048]).unwrap();
+        bf.clear().unwrap();
+        let _ = read_u8_at(&mut bf, 1600);
+        overwrite(&mut f2, 1600, 0x22);
+        //
+        bf.advise(0, 2048, Advice::NoReuse).unwrap();
+        bf.advise(1600, 16, Advice::Normal).unwrap();
+        for i in 0..64 {
+            let _ = read_u8_at(&mut bf, i * 16);
+        }
+        assert_eq!(read_u8_at(&mut bf, 1600), 0x11);
+        //
+        bf.advise(0, 2048, Advice::Random).unwrap();
+        bf.rewind().unwrap();
+        let mut br = vec![0u8; 1024];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(br, vec![0x11; 1024]);
+    }
+
+    #[named]
+    #[test]
+    fn test_advise_len_zero_to_end() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 8).unwrap();
+        bf.write_all(&[0x11; 1024]).unwrap();
+        bf.clear().unwrap();
+        // zero means up to the end, as posix_fadvise()
+        bf.advise(1000, 0, Advice::WillNeed).unwrap();
+        overwrite(&mut f2, 1010, 0x22);
+        assert_eq!(read_u8_at(&mut bf, 1010), 0x11);
+        bf.advise(992, 0, Advice::DontNeed).unwrap();
+        assert_eq!(read_u8_at(&mut bf, 1010), 0x22);
+        // the huge ranges are clamped to the end
+        bf.advise(10, u64::MAX, Advice::WillNeed).unwrap();
+        bf.advise(0, 1 << 40, Advice::WillNeed).unwrap();
+        bf.advise(u64::MAX - 10, 100, Advice::Sequential).unwrap();
+        bf.advise(10, u64::MAX, Advice::DontNeed).unwrap();
+    }
+}
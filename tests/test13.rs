@@ -0,0 +1,118 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test13 {
+    use function_name::named;
+    use rabuf::{BufFile, FileSync, SmallRead};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    fn read_file_at(f: &mut std::fs::File, offset: u64) -> u8 {
+        let mut buf = [0u8; 1];
+        f.seek(SeekFrom::Start(offset)).unwrap();
+        f.read_exact(&mut buf).unwrap();
+        buf[0]
+    }
+
+    fn write_file_at(f: &mut std::fs::File, offset: u64, val: u8) {
+        f.seek(SeekFrom::Start(offset)).unwrap();
+        f.write_all(&[val]).unwrap();
+    }
+
+    fn read_u8_at(bf: &mut BufFile, offset: u64) -> u8 {
+        bf.seek(SeekFrom::Start(offset)).unwrap();
+        bf.read_u8().unwrap()
+    }
+
+    #[named]
+    #[test]
+    fn test_flush_range() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 8).unwrap();
+        bf.write_all(&[0x11; 128]).unwrap();
+        bf.flush().unwrap();
+        //
+        bf.seek(SeekFrom::Start(20)).unwrap();
+        bf.write_all(&[0x22]).unwrap();
+        bf.seek(SeekFrom::Start(100)).unwrap();
+        bf.write_all(&[0x33]).unwrap();
+        bf.flush_range(16, 16).unwrap();
+        assert_eq!(read_file_at(&mut f2, 20), 0x22);
+        assert_eq!(read_file_at(&mut f2, 100), 0x11);
+        //
+        bf.sync_range(90, 20).unwrap();
+        assert_eq!(read_file_at(&mut f2, 100), 0x33);
+        // zero means up to the end
+        bf.seek(SeekFrom::Start(60)).unwrap();
+        bf.write_all(&[0x44]).unwrap();
+        bf.seek(SeekFrom::Start(120)).unwrap();
+        bf.write_all(&[0x55]).unwrap();
+        bf.sync_range(50, 0).unwrap();
+        assert_eq!(read_file_at(&mut f2, 60), 0x44);
+        assert_eq!(read_file_at(&mut f2, 120), 0x55);
+    }
+
+    #[named]
+    #[test]
+    fn test_invalidate_range_flush() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 8).unwrap();
+        bf.write_all(&[0x11; 128]).unwrap();
+        bf.flush().unwrap();
+        bf.seek(SeekFrom::Start(40)).unwrap();
+        bf.write_all(&[0x22]).unwrap();
+        //
+        bf.invalidate_range(32, 32, false).unwrap();
+        assert_eq!(read_file_at(&mut f2, 40), 0x22);
+        // another component rewrites the region
+        write_file_at(&mut f2, 50, 0x44);
+        write_file_at(&mut f2, 100, 0x44);
+        assert_eq!(read_u8_at(&mut bf, 50), 0x44);
+        assert_eq!(read_u8_at(&mut bf, 40), 0x22);
+    }
+
+    #[named]
+    #[test]
+    fn test_invalidate_range_discard() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 16).unwrap();
+        bf.write_all(&[0x11; 128]).unwrap();
+        bf.flush().unwrap();
+        bf.seek(SeekFrom::Start(40)).unwrap();
+        bf.write_all(&[0x22]).unwrap();
+        // extends the file in the cache
+        bf.seek(SeekFrom::End(0)).unwrap();
+        bf.write_all(&[0x33; 40]).unwrap();
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 168);
+        //
+        bf.invalidate_range(0, 1024, true).unwrap();
+        assert_eq!(read_file_at(&mut f2, 40), 0x11);
+        assert_eq!(read_u8_at(&mut bf, 40), 0x11);
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 128);
+    }
+
+    #[named]
+    #[test]
+    fn test_invalidate_range_pinned() {
+        let f = open_test_file!(function_name!());
+        let mut f2 = f.try_clone().unwrap();
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x11; 128]).unwrap();
+        bf.flush().unwrap();
+        bf.pin_range(64, 16).unwrap();
+        //
+        write_file_at(&mut f2, 70, 0x44);
+        bf.invalidate_range(64, 16, false).unwrap();
+        // reloaded, and still pinned
+        write_file_at(&mut f2, 71, 0x55);
+        for i in 0..8 {
+            let _ = read_u8_at(&mut bf, i * 16);
+        }
+        bf.clear().unwrap();
+        assert_eq!(read_u8_at(&mut bf, 70), 0x44);
+        assert_eq!(read_u8_at(&mut bf, 71), 0x11);
+    }
+}
@@ -0,0 +1,215 @@
+#[cfg(test)]
+mod test14 {
+    use rabuf::{FileSetLen, FileSync, RaBuf, RaBufError, RaBufOp};
+    use std::cell::Cell;
+    use std::io::{Cursor, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+    use std::rc::Rc;
+
+    /// In-memory file that fails all of the I/O while `fail` is set.
+    struct FailFile {
+        inner: Cursor<Vec<u8>>,
+        fail: Rc<Cell<bool>>,
+    }
+
+    impl FailFile {
+        fn new(data: Vec<u8>) -> (FailFile, Rc<Cell<bool>>) {
+            let fail = Rc::new(Cell::new(false));
+            let file = FailFile {
+                inner: Cursor::new(data),
+                fail: fail.clone(),
+            };
+            (file, fail)
+        }
+        fn check(&self) -> Result<()> {
+            if self.fail.get() {
+                Err(std::io::Error::new(ErrorKind::PermissionDenied, "injected"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Read for FailFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.check()?;
+            self.inner.read(buf)
+        }
+    }
+
+    impl Write for FailFile {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.check()?;
+            self.inner.write(buf)
+        }
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for FailFile {
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl FileSetLen for FailFile {
+        fn set_len(&mut self, size: u64) -> Result<()> {
+            self.check()?;
+            self.inner.get_mut().resize(size as usize, 0);
+            Ok(())
+        }
+    }
+
+    impl FileSync for FailFile {
+        fn sync_all(&mut self) -> Result<()> {
+            self.check()
+        }
+        fn sync_data(&mut self) -> Result<()> {
+            self.check()
+        }
+    }
+
+    fn rabuf_error(err: std::io::Error) -> RaBufError {
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        RaBufError::from(err)
+    }
+
+    #[test]
+    fn test_load_error() {
+        let (file, fail) = FailFile::new(vec![0x11; 256]);
+        let mut bf = RaBuf::with_capacity("tes-load", file, 64, 4).unwrap();
+        fail.set(true);
+        bf.seek(SeekFrom::Start(130)).unwrap();
+        let mut buf = [0u8; 4];
+        let err = rabuf_error(bf.read_exact(&mut buf).unwrap_err());
+        assert_eq!(err.name(), "tes-load");
+        assert_eq!(err.op(), RaBufOp::Load);
+        assert_eq!(err.offset(), 128);
+        assert_eq!(err.len(), 64);
+        assert_eq!(
+            format!("{}", err),
+            "rabuf \"tes-load\": load failed at offset 128 len 64: injected"
+        );
+        //
+        fail.set(false);
+        bf.seek(SeekFrom::Start(130)).unwrap();
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x11; 4]);
+    }
+
+    #[test]
+    fn test_write_back_error() {
+        let (file, fail) = FailFile::new(vec![0x11; 100]);
+        let mut bf = RaBuf::with_capacity("tes-wb", file, 64, 4).unwrap();
+        bf.seek(SeekFrom::Start(70)).unwrap();
+        bf.write_all(&[0x22; 4]).unwrap();
+        fail.set(true);
+        let err = rabuf_error(bf.flush().unwrap_err());
+        assert_eq!(err.name(), "tes-wb");
+        assert_eq!(err.op(), RaBufOp::WriteBack);
+        assert_eq!(err.offset(), 64);
+        // the chunk is clamped to the end of the file
+        assert_eq!(err.len(), 36);
+        fail.set(false);
+        bf.flush().unwrap();
+    }
+
+    #[test]
+    fn test_set_len_and_sync_error() {
+        let (file, fail) = FailFile::new(vec![0x11; 100]);
+        let mut bf = RaBuf::with_capacity("tes-len", file, 64, 4).unwrap();
+        fail.set(true);
+        let err = rabuf_error(bf.set_len(200).unwrap_err());
+        assert_eq!(err.op(), RaBufOp::SetLen);
+        assert_eq!(err.offset(), 200);
+        //
+        let err = rabuf_error(bf.sync_all().unwrap_err());
+        assert_eq!(err.name(), "tes-len");
+        assert_eq!(err.op(), RaBufOp::Sync);
+        fail.set(false);
+    }
+
+    #[test]
+    fn test_poisoned_and_retry() {
+        let (file, fail) = FailFile::new(vec![0x11; 256]);
+        let mut bf = RaBuf::with_capacity("tes", file, 64, 2).unwrap();
+        bf.write_all(&[0x22; 4]).unwrap();
+        bf.seek(SeekFrom::Start(64)).unwrap();
+        bf.write_all(&[0x33; 4]).unwrap();
+        // the eviction of the dirty chunk fails
+        fail.set(true);
+        bf.seek(SeekFrom::Start(128)).unwrap();
+        let err = bf.write_all(&[0x44; 4]).unwrap_err();
+        assert_eq!(RaBufError::from(err).op(), RaBufOp::WriteBack);
+        assert!(bf.is_poisoned());
+        // the cached chunk is not accessible while poisoned
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = [0u8; 4];
+        let err = bf.read_exact(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(bf.set_len(10).unwrap_err().kind(), ErrorKind::Other);
+        // the unwritten data is kept, and written by the retry
+        assert!(bf.flush().is_err());
+        fail.set(false);
+        bf.flush().unwrap();
+        assert!(!bf.is_poisoned());
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x22; 4]);
+        bf.seek(SeekFrom::Start(64)).unwrap();
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x33; 4]);
+    }
+
+    #[test]
+    fn test_take_unwritten() {
+        let (file, fail) = FailFile::new(vec![0x11; 100]);
+        let mut bf = RaBuf::with_capacity("tes", file, 64, 4).unwrap();
+        bf.seek(SeekFrom::Start(96)).unwrap();
+        bf.write_all(&[0x22; 8]).unwrap();
+        bf.seek(SeekFrom::Start(2)).unwrap();
+        bf.write_all(&[0x33; 2]).unwrap();
+        fail.set(true);
+        assert!(bf.flush().is_err());
+        assert!(bf.is_poisoned());
+        let vec = bf.take_unwritten().unwrap();
+        assert!(!bf.is_poisoned());
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec[0].0, 0);
+        assert_eq!(&vec[0].1[..6], &[0x11, 0x11, 0x33, 0x33, 0x11, 0x11]);
+        assert_eq!(vec[1].0, 64);
+        assert_eq!(vec[1].1.len(), 40);
+        assert_eq!(&vec[1].1[32..], &[0x22; 8]);
+        // the cache is dropped, and the end is back to the file length
+        fail.set(false);
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 100);
+        let mut buf = [0u8; 4];
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x11; 4]);
+    }
+
+    #[test]
+    fn test_convert_bare_io_error() {
+        let err = std::io::Error::new(ErrorKind::NotFound, "bare");
+        let err = RaBufError::from(err);
+        assert_eq!(err.op(), RaBufOp::Other);
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(err.name(), "");
+        let err: std::io::Error = err.into();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(err.get_ref().unwrap().is::<RaBufError>());
+    }
+
+    #[test]
+    fn test_pin_range_load_error() {
+        let (file, fail) = FailFile::new(vec![0x11; 256]);
+        let mut bf = RaBuf::with_capacity("tes-pin", file, 64, 4).unwrap();
+        fail.set(true);
+        let err = bf.pin_range(64, 128).unwrap_err();
+        let _ = rabuf_error(err);
+        // the pins of the failed call are not left
+        fail.set(false);
+        bf.pin_range(64, 128).unwrap();
+    }
+}
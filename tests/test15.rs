@@ -0,0 +1,94 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test15 {
+    use function_name::named;
+    use rabuf::{RaReader, SmallRead};
+    use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_reader_cursor() {
+        let data = test_data(1000);
+        let mut rr = RaReader::with_capacity("tes", Cursor::new(&data[..]), 64, 4).unwrap();
+        assert_eq!(rr.len(), 1000);
+        rr.seek(SeekFrom::Start(62)).unwrap();
+        assert_eq!(
+            rr.read_u32_le().unwrap(),
+            u32::from_le_bytes([62, 63, 64, 65])
+        );
+        assert_eq!(rr.read_u8().unwrap(), 66);
+        //
+        rr.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        rr.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, data);
+        //
+        rr.seek(SeekFrom::Start(300)).unwrap();
+        let ms = rr.read_exact_maybeslice(10).unwrap();
+        assert_eq!(&ms[..], &data[300..310]);
+    }
+
+    #[test]
+    fn test_reader_end() {
+        let data = test_data(100);
+        let mut rr = RaReader::with_capacity("tes", Cursor::new(&data[..]), 64, 4).unwrap();
+        rr.seek(SeekFrom::End(-2)).unwrap();
+        let err = rr.read_u32_le().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        rr.seek(SeekFrom::End(-2)).unwrap();
+        assert_eq!(rr.read_u16_le().unwrap(), u16::from_le_bytes([98, 99]));
+        assert_eq!(rr.read_u8().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        // beyond the end
+        assert_eq!(rr.seek(SeekFrom::Start(130)).unwrap(), 130);
+        let mut buf = [0u8; 4];
+        assert_eq!(rr.read(&mut buf).unwrap(), 0);
+        assert!(rr.seek(SeekFrom::Current(-131)).is_err());
+        assert_eq!(rr.len(), 100);
+    }
+
+    #[test]
+    fn test_reader_bypass() {
+        let data = test_data(10_000);
+        let mut rr = RaReader::with_capacity("tes", Cursor::new(&data[..]), 256, 4).unwrap();
+        rr.set_bypass_threshold(1024);
+        rr.seek(SeekFrom::Start(512)).unwrap();
+        let mut buf = vec![0u8; 20_000];
+        let mut done = 0;
+        loop {
+            let n = rr.read(&mut buf[done..]).unwrap();
+            if n == 0 {
+                break;
+            }
+            done += n;
+        }
+        assert_eq!(done, 10_000 - 512);
+        assert_eq!(&buf[..done], &data[512..]);
+    }
+
+    #[named]
+    #[test]
+    fn test_reader_read_only_file() {
+        let data = test_data(5000);
+        {
+            let mut f = open_test_file!(function_name!());
+            f.write_all(&data).unwrap();
+        }
+        let path = concat!(base_dir!(), "/", function_name!());
+        let f = std::fs::File::open(path).unwrap();
+        let mut rr = RaReader::with_capacity("tes", f, 512, 2).unwrap();
+        for &off in [4000u64, 10, 2048, 4999].iter() {
+            rr.seek(SeekFrom::Start(off)).unwrap();
+            assert_eq!(rr.read_u8().unwrap(), data[off as usize]);
+        }
+        rr.clear().unwrap();
+        rr.seek(SeekFrom::Start(1000)).unwrap();
+        let mut buf = [0u8; 8];
+        rr.read_exact_small(&mut buf).unwrap();
+        assert_eq!(&buf, &data[1000..1008]);
+    }
+}
@@ -0,0 +1,113 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test16 {
+    use function_name::named;
+    use rabuf::{RaAppender, SmallRead};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn file_len(f: &std::fs::File) -> u64 {
+        f.metadata().unwrap().len()
+    }
+
+    #[named]
+    #[test]
+    fn test_append_and_read() {
+        let f = open_test_file!(function_name!());
+        let f2 = f.try_clone().unwrap();
+        let mut ap = RaAppender::with_capacity("tes", f, 16, 4).unwrap();
+        ap.set_flush_chunks(2);
+        ap.write_all(b"0123456789").unwrap();
+        // the tail is not written yet
+        assert_eq!(file_len(&f2), 0);
+        ap.write_all(b"abcdefghijklmnopqrstuvwxyz").unwrap();
+        // 2 full chunks are written, and the rest stays in the tail
+        assert_eq!(file_len(&f2), 32);
+        assert_eq!(ap.len(), 36);
+        //
+        ap.seek(SeekFrom::Start(8)).unwrap();
+        assert_eq!(ap.read_u32_le().unwrap(), u32::from_le_bytes(*b"89ab"));
+        ap.seek(SeekFrom::Start(30)).unwrap();
+        assert_eq!(ap.read_u32_le().unwrap(), u32::from_le_bytes(*b"uvwx"));
+        let ms = ap.read_exact_maybeslice(2).unwrap();
+        assert_eq!(&ms[..], b"yz");
+        assert_eq!(ap.read_u8().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        // a broken size fails without allocating
+        ap.seek(SeekFrom::Start(30)).unwrap();
+        match ap.read_exact_maybeslice(usize::MAX / 2) {
+            Err(err) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+            Ok(_) => panic!("must fail"),
+        }
+        //
+        ap.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        ap.read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf, b"0123456789abcdefghijklmnopqrstuvwxyz");
+        //
+        ap.flush().unwrap();
+        assert_eq!(file_len(&f2), 36);
+    }
+
+    #[named]
+    #[test]
+    fn test_append_rejects_overwrite() {
+        let f = open_test_file!(function_name!());
+        let mut ap = RaAppender::with_capacity("tes", f, 16, 4).unwrap();
+        ap.write_all(b"0123456789").unwrap();
+        ap.seek(SeekFrom::Start(4)).unwrap();
+        let err = ap.write_all(b"x").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        ap.seek(SeekFrom::End(2)).unwrap();
+        assert!(ap.write_all(b"x").is_err());
+        ap.seek(SeekFrom::End(0)).unwrap();
+        ap.write_all(b"x").unwrap();
+        assert_eq!(ap.len(), 11);
+    }
+
+    #[named]
+    #[test]
+    fn test_append_reopen() {
+        let data: Vec<u8> = (0..100u8).collect();
+        {
+            let f = open_test_file!(function_name!());
+            let mut ap = RaAppender::with_capacity("tes", f, 16, 4).unwrap();
+            ap.write_all(&data[..50]).unwrap();
+            // written on drop
+        }
+        let path = concat!(base_dir!(), "/", function_name!());
+        let f = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .unwrap();
+        let mut ap = RaAppender::with_capacity("tes", f, 16, 4).unwrap();
+        assert_eq!(ap.len(), 50);
+        // the partial last chunk is in the tail
+        ap.seek(SeekFrom::End(0)).unwrap();
+        ap.write_all(&data[50..]).unwrap();
+        ap.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        ap.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[named]
+    #[test]
+    fn test_append_cached_chunk_is_refreshed() {
+        let f = open_test_file!(function_name!());
+        let mut ap = RaAppender::with_capacity("tes", f, 16, 4).unwrap();
+        ap.set_flush_chunks(1);
+        ap.write_all(&[1u8; 20]).unwrap();
+        // the chunk 16 is in the tail, and the chunk 0 is in the file
+        ap.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(ap.read_u8().unwrap(), 1);
+        ap.seek(SeekFrom::End(0)).unwrap();
+        ap.write_all(&[2u8; 20]).unwrap();
+        ap.seek(SeekFrom::Start(16)).unwrap();
+        let mut buf = [0u8; 24];
+        ap.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..4], &[1u8; 4]);
+        assert_eq!(&buf[4..], &[2u8; 20]);
+    }
+}
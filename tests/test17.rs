@@ -0,0 +1,157 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test17 {
+    use function_name::named;
+    use rabuf::{BufFile, Record, RecordFile, RecordLayout, SmallRead, SmallWrite};
+    use std::io::{ErrorKind, Result};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Rec {
+        id: u32,
+        val: u64,
+        tag: u8,
+    }
+
+    impl Record for Rec {
+        const SIZE: usize = 13;
+        fn encode<W: SmallWrite + ?Sized>(&self, w: &mut W) -> Result<()> {
+            w.write_u32_le(self.id)?;
+            w.write_u64_le(self.val)?;
+            w.write_u8(self.tag)
+        }
+        fn decode<S: SmallRead + ?Sized>(r: &mut S) -> Result<Self> {
+            Ok(Rec {
+                id: r.read_u32_le()?,
+                val: r.read_u64_le()?,
+                tag: r.read_u8()?,
+            })
+        }
+    }
+
+    fn rec(i: u32) -> Rec {
+        Rec {
+            id: i,
+            val: (i as u64) * 1_000_000_007,
+            tag: (i % 256) as u8,
+        }
+    }
+
+    #[named]
+    #[test]
+    fn test_record_packed() {
+        let f = open_test_file!(function_name!());
+        let f2 = f.try_clone().unwrap();
+        let bf = BufFile::with_capacity("tes", f, 32, 4).unwrap();
+        let mut rf = RecordFile::<Rec, _>::new(bf, RecordLayout::Packed).unwrap();
+        for i in 0..100 {
+            rf.push(&rec(i)).unwrap();
+        }
+        assert_eq!(rf.len(), 100);
+        assert_eq!(rf.offset_of(3), 39);
+        for i in (0..100).rev() {
+            assert_eq!(rf.get(i as u64).unwrap(), rec(i));
+        }
+        rf.set(50, &rec(5000)).unwrap();
+        assert_eq!(rf.get(50).unwrap(), rec(5000));
+        rf.flush().unwrap();
+        assert_eq!(f2.metadata().unwrap().len(), 1300);
+    }
+
+    #[named]
+    #[test]
+    fn test_record_no_straddle() {
+        let f = open_test_file!(function_name!());
+        let f2 = f.try_clone().unwrap();
+        let bf = BufFile::with_capacity("tes", f, 32, 4).unwrap();
+        let mut rf = RecordFile::<Rec, _>::new(bf, RecordLayout::NoStraddle).unwrap();
+        for i in 0..9 {
+            rf.push(&rec(i)).unwrap();
+        }
+        // 2 records per chunk
+        assert_eq!(rf.offset_of(1), 13);
+        assert_eq!(rf.offset_of(2), 32);
+        assert_eq!(rf.offset_of(8), 128);
+        for i in 0..9 {
+            assert_eq!(rf.get(i as u64).unwrap(), rec(i));
+        }
+        rf.flush().unwrap();
+        assert_eq!(f2.metadata().unwrap().len(), 141);
+        // reopen
+        drop(rf);
+        let bf = BufFile::with_capacity("tes", f2, 32, 4).unwrap();
+        let mut rf = RecordFile::<Rec, _>::new(bf, RecordLayout::NoStraddle).unwrap();
+        assert_eq!(rf.len(), 9);
+        assert_eq!(rf.get(7).unwrap(), rec(7));
+    }
+
+    #[named]
+    #[test]
+    fn test_record_truncate() {
+        let f = open_test_file!(function_name!());
+        let f2 = f.try_clone().unwrap();
+        let bf = BufFile::with_capacity("tes", f, 32, 4).unwrap();
+        let mut rf = RecordFile::<Rec, _>::new(bf, RecordLayout::NoStraddle).unwrap();
+        for i in 0..10 {
+            rf.push(&rec(i)).unwrap();
+        }
+        rf.truncate(20).unwrap();
+        assert_eq!(rf.len(), 10);
+        rf.truncate(5).unwrap();
+        assert_eq!(rf.len(), 5);
+        rf.flush().unwrap();
+        assert_eq!(f2.metadata().unwrap().len(), 64 + 13);
+        rf.push(&rec(100)).unwrap();
+        assert_eq!(rf.get(5).unwrap(), rec(100));
+        assert_eq!(rf.get(4).unwrap(), rec(4));
+        rf.truncate(0).unwrap();
+        assert!(rf.is_empty());
+    }
+
+    #[named]
+    #[test]
+    fn test_record_errors() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 8, 4).unwrap();
+        let err = RecordFile::<Rec, _>::new(bf, RecordLayout::NoStraddle).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        //
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 8, 4).unwrap();
+        let mut rf = RecordFile::<Rec, _>::new(bf, RecordLayout::Packed).unwrap();
+        rf.push(&rec(1)).unwrap();
+        assert_eq!(rf.get(1).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            rf.set(2, &rec(2)).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(rf.get(0).unwrap(), rec(1));
+    }
+    // SIZE says 8, but it encodes 4 bytes.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Short(u32);
+
+    impl Record for Short {
+        const SIZE: usize = 8;
+        fn encode<W: SmallWrite + ?Sized>(&self, w: &mut W) -> Result<()> {
+            w.write_u32_le(self.0)
+        }
+        fn decode<S: SmallRead + ?Sized>(r: &mut S) -> Result<Self> {
+            let v = r.read_u32_le()?;
+            let _ = r.read_u32_le()?;
+            Ok(Short(v))
+        }
+    }
+
+    #[named]
+    #[test]
+    fn test_record_mis_sized_encoder() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 32, 4).unwrap();
+        let mut rf = RecordFile::<Short, _>::new(bf, RecordLayout::Packed).unwrap();
+        let err = rf.push(&Short(1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(rf.len(), 0);
+    }
+}
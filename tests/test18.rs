@@ -0,0 +1,116 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test18 {
+    use function_name::named;
+    use rabuf::{BufFile, RaDecode, RaEncode};
+    use std::io::{ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Header {
+        magic: [u8; 4],
+        version: u16,
+        flags: (bool, i32),
+    }
+
+    impl RaEncode for Header {
+        const FIXED_SIZE: Option<usize> = Some(11);
+        fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+            self.magic.encode(w)?;
+            self.version.encode(w)?;
+            self.flags.encode(w)
+        }
+    }
+
+    impl RaDecode for Header {
+        const FIXED_SIZE: Option<usize> = Some(11);
+        fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+            Ok(Header {
+                magic: RaDecode::decode(r)?,
+                version: RaDecode::decode(r)?,
+                flags: RaDecode::decode(r)?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_fixed_size() {
+        assert_eq!(<u32 as RaEncode>::FIXED_SIZE, Some(4));
+        assert_eq!(<[u64; 3] as RaDecode>::FIXED_SIZE, Some(24));
+        assert_eq!(<(u8, f64, [i16; 2]) as RaEncode>::FIXED_SIZE, Some(13));
+        assert_eq!(<(u8, String) as RaEncode>::FIXED_SIZE, None);
+        assert_eq!(<Option<u8> as RaDecode>::FIXED_SIZE, None);
+        assert_eq!(<Vec<u8> as RaDecode>::FIXED_SIZE, None);
+    }
+
+    #[named]
+    #[test]
+    fn test_values_round_trip() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        let hdr = Header {
+            magic: *b"RABF",
+            version: 3,
+            flags: (true, -5),
+        };
+        // values straddle the chunk boundaries
+        for _ in 0..5 {
+            bf.write_value(&hdr).unwrap();
+        }
+        bf.write_value(&Some(1.5f64)).unwrap();
+        bf.write_value(&None::<u32>).unwrap();
+        bf.write_value(&vec![1u16, 2, 3]).unwrap();
+        bf.write_value(&"hello, world".to_string()).unwrap();
+        bf.write_value(&u128::MAX).unwrap();
+        //
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        for _ in 0..5 {
+            assert_eq!(bf.read_value::<Header>().unwrap(), hdr);
+        }
+        assert_eq!(bf.read_value::<Option<f64>>().unwrap(), Some(1.5));
+        assert_eq!(bf.read_value::<Option<u32>>().unwrap(), None);
+        assert_eq!(bf.read_value::<Vec<u16>>().unwrap(), vec![1, 2, 3]);
+        assert_eq!(bf.read_value::<String>().unwrap(), "hello, world");
+        assert_eq!(bf.read_value::<u128>().unwrap(), u128::MAX);
+    }
+
+    #[named]
+    #[test]
+    fn test_value_layout() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_value(&(0x0102u16, true)).unwrap();
+        bf.write_value(&Some(7u8)).unwrap();
+        bf.write_value(&"ab".to_string()).unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            vec![0x02, 0x01, 1, 1, 7, 2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']
+        );
+    }
+
+    #[named]
+    #[test]
+    fn test_decode_errors() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[2u8]).unwrap();
+        bf.write_value(&3u64).unwrap();
+        bf.write_all(&[0xff, 0xfe, 0xfd]).unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let err = bf.read_value::<bool>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        bf.seek(SeekFrom::Start(1)).unwrap();
+        let err = bf.read_value::<String>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        // the length is over the end
+        bf.seek(SeekFrom::Start(1)).unwrap();
+        bf.write_value(&1000u64).unwrap();
+        bf.seek(SeekFrom::Start(1)).unwrap();
+        let err = bf.read_value::<String>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
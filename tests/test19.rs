@@ -0,0 +1,109 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test19 {
+    use function_name::named;
+    use rabuf::{BufFile, LenWidth, MaybeSlice, SmallRead, SmallWrite};
+    use std::borrow::Cow;
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    const WIDTHS: [LenWidth; 5] = [
+        LenWidth::U8,
+        LenWidth::U16,
+        LenWidth::U32,
+        LenWidth::U64,
+        LenWidth::Varint,
+    ];
+
+    #[named]
+    #[test]
+    fn test_bytes_round_trip() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        let long = vec![0x5au8; 200];
+        for width in WIDTHS.iter() {
+            bf.write_bytes_prefixed(b"", *width).unwrap();
+            bf.write_bytes_prefixed(b"abc", *width).unwrap();
+            bf.write_bytes_prefixed(&long, *width).unwrap();
+        }
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        for width in WIDTHS.iter() {
+            assert!(bf.read_bytes_prefixed(*width).unwrap().is_empty());
+            assert_eq!(&*bf.read_bytes_prefixed(*width).unwrap(), b"abc");
+            assert_eq!(&*bf.read_bytes_prefixed(*width).unwrap(), &long[..]);
+        }
+    }
+
+    #[named]
+    #[test]
+    fn test_layout() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_bytes_prefixed(b"a", LenWidth::U16).unwrap();
+        bf.write_bytes_prefixed(&[0u8; 300], LenWidth::Varint)
+            .unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = [0u8; 5];
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 0, b'a', 0xac, 0x02]);
+        //
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        bf.write_u8(0xff).unwrap();
+        bf.write_all(&[0xff; 8]).unwrap();
+        bf.write_u8(0x02).unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let err = bf.read_bytes_prefixed(LenWidth::Varint).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[named]
+    #[test]
+    fn test_str_borrowed_or_owned() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_str_prefixed("hello", LenWidth::U8).unwrap();
+        // straddles the chunk boundary
+        bf.write_str_prefixed("\u{3042}\u{3044}\u{3046}", LenWidth::U32)
+            .unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        match bf.read_str_prefixed(LenWidth::U8).unwrap() {
+            Cow::Borrowed(s) => assert_eq!(s, "hello"),
+            Cow::Owned(_) => panic!("must be borrowed"),
+        }
+        match bf.read_str_prefixed(LenWidth::U32).unwrap() {
+            Cow::Owned(s) => assert_eq!(s, "\u{3042}\u{3044}\u{3046}"),
+            Cow::Borrowed(_) => panic!("must be owned"),
+        }
+        match bf.read_bytes_prefixed(LenWidth::U8) {
+            Err(err) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+            Ok(_) => panic!("must be eof"),
+        }
+    }
+
+    #[named]
+    #[test]
+    fn test_errors() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        let err = bf
+            .write_bytes_prefixed(&[0u8; 256], LenWidth::U8)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        // invalid UTF-8
+        bf.write_bytes_prefixed(&[0xff, 0xfe], LenWidth::U8)
+            .unwrap();
+        // a broken length is not allocated
+        bf.write_u64_le(u64::MAX / 2).unwrap();
+        bf.write_all(b"abc").unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let err = bf.read_str_prefixed(LenWidth::U8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = bf.read_bytes_prefixed(LenWidth::U64).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        match bf.read_exact_maybeslice(usize::MAX / 2) {
+            Err(err) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+            Ok(MaybeSlice::Slice(_)) | Ok(MaybeSlice::Buffer(_)) => panic!("must be eof"),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test20 {
+    use function_name::named;
+    use rabuf::{BufFile, RegionFile};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn write_at(rf: &mut RegionFile<std::fs::File>, offset: u64, buf: &[u8]) {
+        rf.get_mut().seek(SeekFrom::Start(offset)).unwrap();
+        rf.get_mut().write_all(buf).unwrap();
+    }
+
+    fn read_at(rf: &mut RegionFile<std::fs::File>, offset: u64, len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; len];
+        rf.get_mut().seek(SeekFrom::Start(offset)).unwrap();
+        rf.get_mut().read_exact(&mut buf).unwrap();
+        buf
+    }
+
+    fn file_len(rf: &mut RegionFile<std::fs::File>) -> u64 {
+        rf.get_mut().seek(SeekFrom::End(0)).unwrap()
+    }
+
+    #[named]
+    #[test]
+    fn test_alloc_free_reuse() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        let a = rf.alloc(10).unwrap();
+        let b = rf.alloc(100).unwrap();
+        let c = rf.alloc(10).unwrap();
+        assert!(a < b && b < c);
+        assert_eq!(rf.capacity(a).unwrap(), 16);
+        assert!(rf.capacity(b).unwrap() >= 100);
+        let len = file_len(&mut rf);
+        rf.free(b).unwrap();
+        // the freed block is split
+        let d = rf.alloc(20).unwrap();
+        assert_eq!(d, b);
+        let e = rf.alloc(20).unwrap();
+        assert!(e > d && e < c);
+        assert_eq!(file_len(&mut rf), len);
+    }
+
+    #[named]
+    #[test]
+    fn test_coalesce_and_compact() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        let empty_len = file_len(&mut rf);
+        let a = rf.alloc(40).unwrap();
+        let b = rf.alloc(40).unwrap();
+        let c = rf.alloc(40).unwrap();
+        let d = rf.alloc(40).unwrap();
+        rf.free(a).unwrap();
+        rf.free(c).unwrap();
+        rf.free(b).unwrap();
+        // a, b and c are coalesced into one block
+        let x = rf.alloc(150).unwrap();
+        assert_eq!(x, a);
+        rf.free(x).unwrap();
+        rf.free(d).unwrap();
+        // the whole is one free block at the end
+        rf.compact().unwrap();
+        assert_eq!(file_len(&mut rf), empty_len);
+        let y = rf.alloc(40).unwrap();
+        assert_eq!(y, a);
+    }
+
+    #[named]
+    #[test]
+    fn test_realloc() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        let a = rf.alloc(30).unwrap();
+        let b = rf.alloc(30).unwrap();
+        write_at(&mut rf, a, b"ABCDEFGHIJ");
+        write_at(&mut rf, b, b"abcdefghij");
+        // shrink and grow in place
+        assert_eq!(rf.realloc(a, 10).unwrap(), a);
+        assert_eq!(rf.realloc(a, 30).unwrap(), a);
+        // the last block grows by extending the file
+        assert_eq!(rf.realloc(b, 300).unwrap(), b);
+        assert_eq!(rf.capacity(b).unwrap(), 304);
+        // moves
+        let a2 = rf.realloc(a, 200).unwrap();
+        assert_ne!(a2, a);
+        assert_eq!(read_at(&mut rf, a2, 10), b"ABCDEFGHIJ");
+        assert_eq!(read_at(&mut rf, b, 10), b"abcdefghij");
+        // the old block is reused
+        assert_eq!(rf.alloc(30).unwrap(), a);
+    }
+
+    #[named]
+    #[test]
+    fn test_reopen() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        let a = rf.alloc(50).unwrap();
+        let b = rf.alloc(50).unwrap();
+        write_at(&mut rf, b, b"persist");
+        rf.free(a).unwrap();
+        rf.flush().unwrap();
+        drop(rf);
+        //
+        let path = concat!(base_dir!(), "/", function_name!());
+        let f = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .unwrap();
+        let bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        assert_eq!(read_at(&mut rf, b, 7), b"persist");
+        assert_eq!(rf.alloc(50).unwrap(), a);
+    }
+
+    #[named]
+    #[test]
+    fn test_errors() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        bf.write_all(b"not a region file").unwrap();
+        let err = RegionFile::new(bf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        //
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        let a = rf.alloc(10).unwrap();
+        let _b = rf.alloc(10).unwrap();
+        rf.free(a).unwrap();
+        let err = rf.free(a).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = rf.free(a + 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = rf.free(0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = rf.realloc(a, 20).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = rf.alloc(u64::MAX - 8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[named]
+    #[test]
+    fn test_random() {
+        let f = open_test_file!(function_name!());
+        let bf = BufFile::with_capacity("tes", f, 256, 8).unwrap();
+        let mut rf = RegionFile::new(bf).unwrap();
+        let mut seed = 12345u64;
+        let mut rand = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            seed >> 33
+        };
+        let mut live: Vec<(u64, Vec<u8>)> = Vec::new();
+        for i in 0..2000 {
+            match rand() % 4 {
+                0 | 1 => {
+                    let size = (rand() % 600) as usize;
+                    let data = vec![(i % 251) as u8; size];
+                    let off = rf.alloc(size as u64).unwrap();
+                    write_at(&mut rf, off, &data);
+                    live.push((off, data));
+                }
+                2 if !live.is_empty() => {
+                    let idx = (rand() as usize) % live.len();
+                    let (off, _) = live.swap_remove(idx);
+                    rf.free(off).unwrap();
+                }
+                3 if !live.is_empty() => {
+                    let idx = (rand() as usize) % live.len();
+                    let size = (rand() % 600) as usize;
+                    let (off, mut data) = live[idx].clone();
+                    let off = rf.realloc(off, size as u64).unwrap();
+                    data.resize(size, (i % 251) as u8);
+                    write_at(&mut rf, off, &data);
+                    live[idx] = (off, data);
+                }
+                _ => {}
+            }
+        }
+        // no region overlaps the others
+        let mut ranges: Vec<(u64, u64)> = live
+            .iter()
+            .map(|(off, data)| (*off, *off + data.len() as u64))
+            .collect();
+        ranges.sort_unstable();
+        for w in ranges.windows(2) {
+            assert!(w[0].1 <= w[1].0, "{:?}", w);
+        }
+        for (off, data) in live.iter() {
+            assert_eq!(&read_at(&mut rf, *off, data.len()), data);
+        }
+        for (off, _) in live.iter() {
+            rf.free(*off).unwrap();
+        }
+        rf.compact().unwrap();
+        let len = file_len(&mut rf);
+        let bf = rf.get_mut();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut header = Vec::new();
+        bf.read_to_end(&mut header).unwrap();
+        // all the free lists are empty
+        assert_eq!(len, header.len() as u64);
+        assert!(header[8..].iter().all(|&x| x == 0));
+    }
+}
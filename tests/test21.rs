@@ -0,0 +1,133 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test21 {
+    use function_name::named;
+    use rabuf::{FileSetLen, FileSync, SegmentedBufFile, SegmentedFile};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    macro_rules! open_segmented {
+        ($fnm:expr, $seg_sz:expr) => {{
+            std::fs::create_dir_all(base_dir!()).unwrap();
+            let base = concat!(base_dir!(), "/", $fnm);
+            for i in 0..10 {
+                let _ = std::fs::remove_file(format!("{}.{:03}", base, i));
+            }
+            SegmentedFile::open(base, $seg_sz).unwrap()
+        }};
+    }
+
+    fn segment_lens(sf: &SegmentedFile) -> Vec<u64> {
+        (0..10)
+            .map(|i| sf.segment_path(i))
+            .take_while(|p| p.exists())
+            .map(|p| std::fs::metadata(p).unwrap().len())
+            .collect()
+    }
+
+    #[named]
+    #[test]
+    fn test_write_across_segments() {
+        let sf = open_segmented!(function_name!(), 256);
+        assert_eq!(sf.num_segments(), 1);
+        let mut bf = SegmentedBufFile::with_segments("tes", sf, 64, 4).unwrap();
+        let data: Vec<u8> = (0..700).map(|i| (i % 251) as u8).collect();
+        bf.write_all(&data).unwrap();
+        bf.sync_all().unwrap();
+        //
+        let base = concat!(base_dir!(), "/", function_name!());
+        let sf = SegmentedFile::open(base, 256).unwrap();
+        assert_eq!(sf.num_segments(), 3);
+        assert_eq!(segment_lens(&sf), vec![256, 256, 188]);
+        let mut bf = SegmentedBufFile::with_segments("tes", sf, 64, 4).unwrap();
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 700);
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[named]
+    #[test]
+    fn test_write_past_end() {
+        let mut sf = open_segmented!(function_name!(), 128);
+        // the previous segments are filled to full.
+        sf.seek(SeekFrom::Start(300)).unwrap();
+        sf.write_all(b"abc").unwrap();
+        assert_eq!(segment_lens(&sf), vec![128, 128, 47]);
+        sf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        sf.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf.len(), 303);
+        assert!(buf[..300].iter().all(|&x| x == 0));
+        assert_eq!(&buf[300..], b"abc");
+    }
+
+    #[named]
+    #[test]
+    fn test_set_len() {
+        let sf = open_segmented!(function_name!(), 128);
+        let mut bf = SegmentedBufFile::with_segments("tes", sf, 32, 4).unwrap();
+        bf.write_all(&[1u8; 500]).unwrap();
+        bf.flush().unwrap();
+        // shrink removes the segments
+        bf.set_len(130).unwrap();
+        bf.flush().unwrap();
+        let base = concat!(base_dir!(), "/", function_name!());
+        let sf = SegmentedFile::open(base, 128).unwrap();
+        assert_eq!(segment_lens(&sf), vec![128, 2]);
+        drop(sf);
+        // extend creates the segments
+        bf.set_len(400).unwrap();
+        bf.sync_data().unwrap();
+        let sf = SegmentedFile::open(base, 128).unwrap();
+        assert_eq!(segment_lens(&sf), vec![128, 128, 128, 16]);
+        drop(sf);
+        bf.seek(SeekFrom::Start(128)).unwrap();
+        let mut buf = [0xffu8; 4];
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 1, 0, 0]);
+        //
+        bf.set_len(0).unwrap();
+        let sf = SegmentedFile::open(base, 128).unwrap();
+        assert_eq!(segment_lens(&sf), vec![0]);
+    }
+
+    #[named]
+    #[test]
+    fn test_errors() {
+        let sf = open_segmented!(function_name!(), 100);
+        let err = SegmentedBufFile::with_segments("tes", sf, 64, 4).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = SegmentedFile::open(base_dir!(), 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        // a segment that is not full before the last
+        let base = concat!(base_dir!(), "/", function_name!());
+        let mut sf = SegmentedFile::open(base, 100).unwrap();
+        sf.write_all(&[0u8; 150]).unwrap();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(sf.segment_path(0))
+            .unwrap()
+            .set_len(10)
+            .unwrap();
+        let err = SegmentedFile::open(base, 100).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+    #[named]
+    #[test]
+    fn test_set_len_remove_fails() {
+        let mut sf = open_segmented!(function_name!(), 100);
+        sf.write_all(&[1u8; 350]).unwrap();
+        assert_eq!(sf.num_segments(), 4);
+        // the segment 1 can not be removed
+        std::fs::remove_file(sf.segment_path(1)).unwrap();
+        assert!(sf.set_len(50).is_err());
+        // the segments 3 and 2 are removed, and the segment 1 stays
+        assert_eq!(sf.num_segments(), 2);
+        assert!(!sf.segment_path(2).exists());
+        assert_eq!(sf.seek(SeekFrom::End(0)).unwrap(), 200);
+        sf.sync_all().unwrap();
+    }
+}
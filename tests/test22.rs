@@ -0,0 +1,139 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test22 {
+    use function_name::named;
+    use rabuf::{BufFile, SegmentedBufFile, SegmentedFile};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn read_all(bf: &mut BufFile) -> Vec<u8> {
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[named]
+    #[test]
+    fn test_copy_within_overlap() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let data = pattern(1000);
+        bf.write_all(&data).unwrap();
+        bf.seek(SeekFrom::Start(10)).unwrap();
+        // forward and backward, across the chunk boundaries
+        let cases: [(u64, u64, u64); 4] =
+            [(100, 130, 500), (130, 100, 500), (0, 1, 999), (5, 0, 995)];
+        let mut expected = data;
+        for &(src, dst, len) in cases.iter() {
+            bf.copy_within(src, dst, len).unwrap();
+            expected.copy_within((src as usize)..((src + len) as usize), dst as usize);
+        }
+        assert_eq!(bf.stream_position().unwrap(), 10);
+        assert_eq!(read_all(&mut bf), expected);
+    }
+
+    #[named]
+    #[test]
+    fn test_copy_within_kernel() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 2).unwrap();
+        let data = pattern(3000);
+        bf.write_all(&data).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        // the ranges are not cached, and extend the file
+        bf.copy_within(100, 2900, 1000).unwrap();
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 3900);
+        let mut expected = data;
+        expected.extend_from_slice(&[0u8; 900]);
+        expected.copy_within(100..1100, 2900);
+        assert_eq!(read_all(&mut bf), expected);
+        bf.flush().unwrap();
+        let mut file_data = Vec::new();
+        let path = concat!(base_dir!(), "/", function_name!());
+        std::fs::File::open(path)
+            .unwrap()
+            .read_to_end(&mut file_data)
+            .unwrap();
+        assert_eq!(file_data, expected);
+    }
+
+    #[named]
+    #[test]
+    fn test_copy_range_to() {
+        let f = open_test_file!(concat!(function_name!(), "_src"));
+        let mut src = BufFile::with_capacity("src", f, 64, 4).unwrap();
+        let f = open_test_file!(concat!(function_name!(), "_dst"));
+        let mut dst = BufFile::with_capacity("dst", f, 128, 4).unwrap();
+        let data = pattern(2000);
+        src.write_all(&data).unwrap();
+        dst.write_all(&[0xffu8; 100]).unwrap();
+        // through the cache: the source chunks are dirty
+        src.copy_range_to(&mut dst, 30, 50, 700).unwrap();
+        // in the kernel: no chunk is cached
+        src.flush().unwrap();
+        src.invalidate_range(0, u64::MAX, false).unwrap();
+        dst.flush().unwrap();
+        dst.invalidate_range(0, u64::MAX, false).unwrap();
+        src.copy_range_to(&mut dst, 1000, 800, 1000).unwrap();
+        //
+        let mut expected = vec![0xffu8; 50];
+        expected.extend_from_slice(&data[30..730]);
+        expected.extend_from_slice(&[0u8; 50]);
+        expected.extend_from_slice(&data[1000..2000]);
+        assert_eq!(read_all(&mut dst), expected);
+        assert_eq!(src.stream_position().unwrap(), 2000);
+    }
+
+    #[named]
+    #[test]
+    fn test_copy_range_to_segmented() {
+        let f = open_test_file!(function_name!());
+        let mut src = BufFile::with_capacity("src", f, 64, 4).unwrap();
+        let data = pattern(500);
+        src.write_all(&data).unwrap();
+        src.flush().unwrap();
+        src.invalidate_range(0, u64::MAX, false).unwrap();
+        //
+        let base = concat!(base_dir!(), "/", function_name!(), "_seg");
+        for i in 0..4 {
+            let _ = std::fs::remove_file(format!("{}.{:03}", base, i));
+        }
+        let sf = SegmentedFile::open(base, 128).unwrap();
+        let mut dst = SegmentedBufFile::with_segments("dst", sf, 32, 4).unwrap();
+        src.copy_range_to(&mut dst, 0, 0, 500).unwrap();
+        dst.flush().unwrap();
+        dst.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        dst.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[named]
+    #[test]
+    fn test_copy_errors() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        bf.write_all(&pattern(100)).unwrap();
+        let err = bf.copy_within(50, 0, 51).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = bf.copy_within(u64::MAX, 0, 2).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        bf.copy_within(100, 0, 0).unwrap();
+        // the destination overflows
+        let err = bf.copy_within(0, u64::MAX - 10, 50).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let f = open_test_file!(concat!(function_name!(), "_dst"));
+        let mut dst = BufFile::with_capacity("dst", f, 64, 4).unwrap();
+        let err = bf
+            .copy_range_to(&mut dst, 0, u64::MAX - 10, 50)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
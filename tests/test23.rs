@@ -0,0 +1,126 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test23 {
+    use function_name::named;
+    use rabuf::{BufFile, FileSetLen};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn read_all(bf: &mut BufFile) -> Vec<u8> {
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[named]
+    #[test]
+    fn test_insert_remove() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(b"0123456789").unwrap();
+        bf.seek(SeekFrom::Start(3)).unwrap();
+        bf.insert_bytes(5, b"abc").unwrap();
+        bf.insert_bytes(0, b"<").unwrap();
+        bf.insert_bytes(14, b">").unwrap();
+        assert_eq!(bf.stream_position().unwrap(), 3);
+        assert_eq!(read_all(&mut bf), b"<01234abc56789>");
+        //
+        bf.remove_bytes(6, 3).unwrap();
+        bf.remove_bytes(0, 1).unwrap();
+        bf.remove_bytes(10, 1).unwrap();
+        assert_eq!(read_all(&mut bf), b"0123456789");
+        bf.flush().unwrap();
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), b"0123456789");
+        // the position is clamped to the new end
+        bf.seek(SeekFrom::Start(10)).unwrap();
+        bf.remove_bytes(2, 5).unwrap();
+        assert_eq!(bf.stream_position().unwrap(), 5);
+        assert_eq!(read_all(&mut bf), b"01789");
+    }
+
+    #[named]
+    #[test]
+    fn test_random_edits() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 32, 3).unwrap();
+        let mut model: Vec<u8> = (0..300).map(|i| (i % 251) as u8).collect();
+        bf.write_all(&model).unwrap();
+        let mut seed = 7u64;
+        let mut rand = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) as usize
+        };
+        for i in 0..300 {
+            let offset = rand() % (model.len() + 1);
+            if rand() % 2 == 0 {
+                let buf = vec![(i % 256) as u8; rand() % 100];
+                bf.insert_bytes(offset as u64, &buf).unwrap();
+                model.splice(offset..offset, buf);
+            } else {
+                let len = rand() % (model.len() - offset + 1);
+                bf.remove_bytes(offset as u64, len as u64).unwrap();
+                model.drain(offset..(offset + len));
+            }
+        }
+        assert_eq!(read_all(&mut bf), model);
+        bf.flush().unwrap();
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), model);
+    }
+
+    #[named]
+    #[test]
+    fn test_errors() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(b"0123456789").unwrap();
+        let err = bf.insert_bytes(11, b"x").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = bf.remove_bytes(5, 6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        bf.insert_bytes(10, b"").unwrap();
+        bf.remove_bytes(10, 0).unwrap();
+        assert_eq!(read_all(&mut bf), b"0123456789");
+    }
+
+    #[named]
+    #[test]
+    fn test_truncated_behind() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x5au8; 100]).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        // truncated by the other, not by the dirty chunks after it
+        let path = concat!(base_dir!(), "/", function_name!());
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_len(40)
+            .unwrap();
+        bf.seek(SeekFrom::Start(50)).unwrap();
+        let mut buf = [0u8; 4];
+        let err = bf.read_exact(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+    #[named]
+    #[test]
+    fn test_remove_then_extend() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x5au8; 40]).unwrap();
+        // the chunk 32 is past the new end, and the chunk 16 is over it
+        bf.remove_bytes(0, 20).unwrap();
+        bf.set_len(40).unwrap();
+        let mut expected = vec![0x5au8; 20];
+        expected.resize(40, 0);
+        assert_eq!(read_all(&mut bf), expected);
+        bf.flush().unwrap();
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), expected);
+    }
+}
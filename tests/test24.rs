@@ -0,0 +1,95 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test24 {
+    use function_name::named;
+    use rabuf::{BufFile, SmallWrite};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn read_all(bf: &mut BufFile) -> Vec<u8> {
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[named]
+    #[test]
+    fn test_fill() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        bf.write_all(&[0xffu8; 40]).unwrap();
+        bf.seek(SeekFrom::Start(3)).unwrap();
+        bf.fill(5, 30, b"abc").unwrap();
+        bf.fill(0, 2, &[7]).unwrap();
+        // beyond the end extends the file
+        bf.fill(50, 10, b"xy").unwrap();
+        assert_eq!(bf.stream_position().unwrap(), 3);
+        //
+        let mut expected = vec![0xffu8; 40];
+        for i in 0..30 {
+            expected[5 + i] = b"abc"[i % 3];
+        }
+        expected[0] = 7;
+        expected[1] = 7;
+        expected.resize(50, 0);
+        expected.extend_from_slice(b"xyxyxyxyxy");
+        assert_eq!(read_all(&mut bf), expected);
+        bf.flush().unwrap();
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), expected);
+        //
+        let err = bf.fill(0, 1, b"").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        bf.fill(100, 0, b"a").unwrap();
+        assert_eq!(bf.seek(SeekFrom::End(0)).unwrap(), 60);
+    }
+
+    #[named]
+    #[test]
+    fn test_write_zero_large() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        bf.write_all(&[0xffu8; 100]).unwrap();
+        bf.seek(SeekFrom::Start(10)).unwrap();
+        bf.write_zero(70).unwrap();
+        assert_eq!(bf.stream_position().unwrap(), 80);
+        let buf = read_all(&mut bf);
+        assert!(buf[..10].iter().all(|&x| x == 0xff));
+        assert!(buf[10..80].iter().all(|&x| x == 0));
+        assert!(buf[80..].iter().all(|&x| x == 0xff));
+    }
+
+    #[named]
+    #[test]
+    fn test_zero_range() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+        bf.write_all(&[0x5au8; 8 * 4096]).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        // a cached clean copy and a cached dirty copy in the range
+        bf.seek(SeekFrom::Start(3 * 4096)).unwrap();
+        let mut buf = [0u8; 1];
+        bf.read_exact(&mut buf).unwrap();
+        bf.seek(SeekFrom::Start(4 * 4096 + 10)).unwrap();
+        bf.write_all(b"dirty").unwrap();
+        //
+        bf.zero_range(100, 6 * 4096).unwrap();
+        bf.zero_range(8 * 4096 - 10, 30).unwrap();
+        let mut expected = vec![0x5au8; 8 * 4096];
+        expected[(4 * 4096 + 10)..(4 * 4096 + 15)].copy_from_slice(b"dirty");
+        for x in expected[100..(100 + 6 * 4096)].iter_mut() {
+            *x = 0;
+        }
+        for x in expected[(8 * 4096 - 10)..].iter_mut() {
+            *x = 0;
+        }
+        expected.resize(8 * 4096 + 20, 0);
+        assert_eq!(read_all(&mut bf), expected);
+        bf.flush().unwrap();
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), expected);
+    }
+}
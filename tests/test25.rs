@@ -0,0 +1,65 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test25 {
+    use function_name::named;
+    use rabuf::{BufFile, FileSetLen};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[named]
+    #[test]
+    fn test_sparse_read() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+        bf.set_sparse(true);
+        bf.seek(SeekFrom::Start(64 * 4096)).unwrap();
+        bf.write_all(&[0x5au8; 4096]).unwrap();
+        bf.set_len(128 * 4096).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        //
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf.len(), 128 * 4096);
+        assert!(buf[..(64 * 4096)].iter().all(|&x| x == 0));
+        assert!(buf[(64 * 4096)..(65 * 4096)].iter().all(|&x| x == 0x5a));
+        assert!(buf[(65 * 4096)..].iter().all(|&x| x == 0));
+        // a hole chunk is writable
+        bf.seek(SeekFrom::Start(10)).unwrap();
+        bf.write_all(b"abc").unwrap();
+        bf.seek(SeekFrom::Start(8)).unwrap();
+        let mut buf = [0xffu8; 6];
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"\0\0abc\0");
+    }
+
+    #[named]
+    #[test]
+    fn test_next_data_hole() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+        bf.set_len(1024 * 1024).unwrap();
+        bf.seek(SeekFrom::Start(256 * 1024)).unwrap();
+        bf.write_all(&[1u8; 10]).unwrap();
+        // the dirty data is counted
+        let data = bf.next_data(0).unwrap().unwrap();
+        assert!(data <= 256 * 1024);
+        let hole = bf.next_hole(data).unwrap().unwrap();
+        assert!(hole > 256 * 1024 && hole <= 1024 * 1024);
+        assert_eq!(bf.next_hole(1024 * 1024).unwrap(), None);
+        assert_eq!(bf.next_data(1024 * 1024).unwrap(), None);
+        // iterates the extents
+        let mut extents = Vec::new();
+        let mut curr = 0;
+        while let Some(data) = bf.next_data(curr).unwrap() {
+            let hole = bf.next_hole(data).unwrap().unwrap();
+            extents.push((data, hole));
+            curr = hole;
+        }
+        assert!(extents
+            .iter()
+            .any(|&(d, h)| d <= 256 * 1024 && 256 * 1024 + 10 <= h));
+    }
+}
@@ -0,0 +1,89 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test26 {
+    use function_name::named;
+    use rabuf::BufFile;
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[named]
+    #[test]
+    fn test_read_slices() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 8).unwrap();
+        let data = pattern(1000);
+        bf.write_all(&data).unwrap();
+        bf.seek(SeekFrom::Start(7)).unwrap();
+        //
+        let slices = bf.read_slices(50, 200).unwrap();
+        assert_eq!(slices.num_pieces(), 4);
+        assert_eq!(slices.len(), 200);
+        assert!(slices == data[50..250]);
+        let lens: Vec<usize> = slices.clone().map(|x| x.len()).collect();
+        assert_eq!(lens, vec![14, 64, 64, 58]);
+        assert_eq!(slices.to_vec(), &data[50..250]);
+        //
+        let a = bf.read_slices(0, 100).unwrap().to_vec();
+        assert_eq!(a, &data[..100]);
+        assert!(bf.read_slices(1000, 0).unwrap().is_empty());
+        assert_eq!(bf.stream_position().unwrap(), 7);
+    }
+
+    #[named]
+    #[test]
+    fn test_read_slices_read() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 8).unwrap();
+        let data = pattern(500);
+        bf.write_all(&data).unwrap();
+        let mut slices = bf.read_slices(10, 300).unwrap();
+        let mut buf = [0u8; 100];
+        slices.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data[10..110]);
+        // the remaining bytes
+        assert_eq!(slices.len(), 200);
+        assert!(slices == data[110..310].to_vec());
+        assert_eq!(slices.next().unwrap(), &data[110..128]);
+        let mut rest = Vec::new();
+        slices.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &data[128..310]);
+    }
+
+    #[named]
+    #[test]
+    fn test_read_slices_eq() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 8).unwrap();
+        let mut data = pattern(200);
+        data.extend_from_slice(&pattern(200));
+        bf.write_all(&data).unwrap();
+        let a = bf.read_slices(0, 200).unwrap().to_vec();
+        // the different boundaries of the pieces
+        let b = bf.read_slices(200, 200).unwrap();
+        assert!(b == a[..]);
+        assert!(b != a[..199]);
+        let c = bf.read_slices(201, 199).unwrap();
+        assert!(c == a[1..]);
+        assert!(c != a[..199]);
+    }
+
+    #[named]
+    #[test]
+    fn test_read_slices_evicted() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 2).unwrap();
+        let data = pattern(2000);
+        bf.write_all(&data).unwrap();
+        // more chunks than the cache
+        let slices = bf.read_slices(30, 1900).unwrap();
+        assert_eq!(slices.num_pieces(), 31);
+        assert!(slices == data[30..1930]);
+        let err = bf.read_slices(1990, 11).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
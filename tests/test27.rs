@@ -0,0 +1,70 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test27 {
+    use function_name::named;
+    use rabuf::BufFile;
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn read_all(bf: &mut BufFile) -> Vec<u8> {
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[named]
+    #[test]
+    fn test_with_slice() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(b"0123456789abcdefghijklmnopqrstuv").unwrap();
+        bf.seek(SeekFrom::Start(3)).unwrap();
+        // in one chunk
+        let sum = bf
+            .with_slice(2, 4, |x| x.iter().map(|&a| a as u32).sum::<u32>())
+            .unwrap();
+        assert_eq!(sum, (b'2' + b'3' + b'4' + b'5') as u32);
+        // across the chunks
+        let s = bf.with_slice(12, 8, |x| x.to_vec()).unwrap();
+        assert_eq!(s, b"cdefghij");
+        assert_eq!(bf.with_slice(32, 0, |x| x.len()).unwrap(), 0);
+        let err = bf.with_slice(30, 3, |x| x.len()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(bf.stream_position().unwrap(), 3);
+    }
+
+    #[named]
+    #[test]
+    fn test_with_slice_mut() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(b"0123456789abcdefghijklmnopqrstuv").unwrap();
+        bf.flush().unwrap();
+        bf.seek(SeekFrom::Start(3)).unwrap();
+        // in one chunk
+        bf.with_slice_mut(0, 2, |x| x.copy_from_slice(b"AB"))
+            .unwrap();
+        // across the chunks
+        let r = bf
+            .with_slice_mut(14, 4, |x| {
+                x.make_ascii_uppercase();
+                x.len()
+            })
+            .unwrap();
+        assert_eq!(r, 4);
+        // extends the end
+        bf.with_slice_mut(30, 6, |x| x.copy_from_slice(b"UVWXYZ"))
+            .unwrap();
+        bf.with_slice_mut(40, 3, |x| x.fill(b'!')).unwrap();
+        assert_eq!(bf.stream_position().unwrap(), 3);
+        //
+        let mut expected = b"AB23456789abcdEFGHijklmnopqrstUVWXYZ".to_vec();
+        expected.extend_from_slice(b"\0\0\0\0!!!");
+        assert_eq!(read_all(&mut bf), expected);
+        bf.flush().unwrap();
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), expected);
+    }
+}
@@ -0,0 +1,109 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test28 {
+    use function_name::named;
+    use rabuf::BufFile;
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn collect<R: std::ops::RangeBounds<u64>>(bf: &mut BufFile, range: R) -> Vec<(u64, Vec<u8>)> {
+        let mut vec = Vec::new();
+        let mut iter = bf.chunks(range);
+        while let Some(r) = iter.next_chunk() {
+            let (offset, data) = r.unwrap();
+            vec.push((offset, data.to_vec()));
+        }
+        vec
+    }
+
+    #[named]
+    #[test]
+    fn test_chunks() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let data = pattern(300);
+        bf.write_all(&data).unwrap();
+        bf.seek(SeekFrom::Start(5)).unwrap();
+        //
+        let v = collect(&mut bf, ..);
+        let offsets: Vec<u64> = v.iter().map(|x| x.0).collect();
+        assert_eq!(offsets, vec![0, 64, 128, 192, 256]);
+        assert_eq!(v[4].1.len(), 44);
+        let all: Vec<u8> = v.into_iter().flat_map(|x| x.1).collect();
+        assert_eq!(all, data);
+        //
+        let v = collect(&mut bf, 100..=200);
+        let offsets: Vec<u64> = v.iter().map(|x| x.0).collect();
+        assert_eq!(offsets, vec![100, 128, 192]);
+        let all: Vec<u8> = v.into_iter().flat_map(|x| x.1).collect();
+        assert_eq!(all, &data[100..=200]);
+        // clamped to the end
+        assert_eq!(collect(&mut bf, 290..1000).len(), 1);
+        assert!(collect(&mut bf, 300..).is_empty());
+        assert_eq!(bf.stream_position().unwrap(), 5);
+    }
+
+    #[named]
+    #[test]
+    fn test_chunks_scan_keeps_cache() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let mut data = pattern(64 * 100);
+        bf.write_all(&data).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        // the hot chunk is dirty, and not written yet
+        bf.seek(SeekFrom::Start(64 * 50 + 3)).unwrap();
+        bf.write_all(b"hot").unwrap();
+        data[(64 * 50 + 3)..(64 * 50 + 6)].copy_from_slice(b"hot");
+        //
+        let v = collect(&mut bf, ..);
+        assert_eq!(v.len(), 100);
+        let all: Vec<u8> = v.into_iter().flat_map(|x| x.1).collect();
+        assert_eq!(all, data);
+        // the dirty chunk is still cached, not written by the scan
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(
+            &std::fs::read(path).unwrap()[(64 * 50 + 3)..(64 * 50 + 6)],
+            &pattern(64 * 100)[(64 * 50 + 3)..(64 * 50 + 6)]
+        );
+        bf.seek(SeekFrom::Start(64 * 50)).unwrap();
+        let mut buf = vec![0u8; 64];
+        bf.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[(64 * 50)..(64 * 51)]);
+    }
+
+    #[named]
+    #[test]
+    fn test_chunks_truncated_behind() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        bf.write_all(&pattern(64 * 10)).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        // truncated by the other
+        let path = concat!(base_dir!(), "/", function_name!());
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_len(64 * 5 + 10)
+            .unwrap();
+        let mut iter = bf.chunks(..);
+        let mut n = 0;
+        let err = loop {
+            match iter.next_chunk().unwrap() {
+                Ok(_) => n += 1,
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(n, 5);
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert!(iter.next_chunk().is_none());
+    }
+}
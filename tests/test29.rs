@@ -0,0 +1,78 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test29 {
+    use function_name::named;
+    use rabuf::BufFile;
+    use std::io::{ErrorKind, Seek, SeekFrom, Write};
+
+    fn naive_find(hay: &[u8], pat: &[u8], st: usize, ed: usize) -> Option<u64> {
+        (st..ed)
+            .find(|&i| i + pat.len() <= ed && &hay[i..(i + pat.len())] == pat)
+            .map(|i| i as u64)
+    }
+
+    fn naive_rfind(hay: &[u8], pat: &[u8], st: usize, ed: usize) -> Option<u64> {
+        (st..ed)
+            .rev()
+            .find(|&i| i + pat.len() <= ed && &hay[i..(i + pat.len())] == pat)
+            .map(|i| i as u64)
+    }
+
+    #[named]
+    #[test]
+    fn test_find() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(b"0123456789abcdef-MAGIC-0123456789abcMAGICdef")
+            .unwrap();
+        bf.seek(SeekFrom::Start(3)).unwrap();
+        // spans the chunks at 16 and 32
+        assert_eq!(bf.find(b"-MAGIC-", ..).unwrap(), Some(16));
+        assert_eq!(bf.find(b"MAGIC", ..).unwrap(), Some(17));
+        assert_eq!(bf.find(b"MAGIC", 18..).unwrap(), Some(36));
+        assert_eq!(bf.find(b"MAGIC", 18..40).unwrap(), None);
+        assert_eq!(bf.rfind(b"MAGIC", ..).unwrap(), Some(36));
+        assert_eq!(bf.rfind(b"MAGIC", ..40).unwrap(), Some(17));
+        assert_eq!(bf.rfind(b"f-M", ..).unwrap(), Some(15));
+        assert_eq!(bf.find(b"nothing", ..).unwrap(), None);
+        assert_eq!(bf.find_byte(b'-', ..).unwrap(), Some(16));
+        assert_eq!(bf.find_byte(b'-', 17..).unwrap(), Some(22));
+        assert_eq!(bf.find_byte(b'z', ..).unwrap(), None);
+        assert_eq!(bf.stream_position().unwrap(), 3);
+        //
+        let err = bf.find(b"", ..).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[named]
+    #[test]
+    fn test_find_random() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        let mut seed = 11u64;
+        let mut rand = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) as usize
+        };
+        // a small alphabet for many matches
+        let data: Vec<u8> = (0..500).map(|_| b"ab"[rand() % 2]).collect();
+        bf.write_all(&data).unwrap();
+        for _ in 0..300 {
+            let plen = 1 + rand() % 40;
+            let pst = rand() % (data.len() - plen);
+            let pat = data[pst..(pst + plen)].to_vec();
+            let st = rand() % data.len();
+            let ed = st + rand() % (data.len() - st + 1);
+            assert_eq!(
+                bf.find(&pat, (st as u64)..(ed as u64)).unwrap(),
+                naive_find(&data, &pat, st, ed)
+            );
+            assert_eq!(
+                bf.rfind(&pat, (st as u64)..(ed as u64)).unwrap(),
+                naive_rfind(&data, &pat, st, ed)
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test30 {
+    use function_name::named;
+    use rabuf::BufFile;
+    use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+
+    #[named]
+    #[test]
+    fn test_lines() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        let lines: Vec<String> = (0..50)
+            .map(|i| format!("line {} {}", i, "x".repeat(i % 23)))
+            .collect();
+        for line in lines.iter() {
+            writeln!(bf, "{}", line).unwrap();
+        }
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let read: Vec<String> = (&mut bf).lines().map(|x| x.unwrap()).collect();
+        assert_eq!(read, lines);
+        // at the end
+        let mut s = String::new();
+        assert_eq!(bf.read_line(&mut s).unwrap(), 0);
+    }
+
+    #[named]
+    #[test]
+    fn test_fill_buf() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        bf.write_all(b"0123456789abcdefghij").unwrap();
+        bf.seek(SeekFrom::Start(5)).unwrap();
+        // the rest of the chunk
+        assert_eq!(bf.fill_buf().unwrap(), b"56789abcdef");
+        bf.consume(3);
+        assert_eq!(bf.fill_buf().unwrap(), b"89abcdef");
+        bf.consume(8);
+        // clamped to the end
+        assert_eq!(bf.fill_buf().unwrap(), b"ghij");
+        bf.consume(4);
+        assert_eq!(bf.fill_buf().unwrap(), b"");
+        assert_eq!(bf.stream_position().unwrap(), 20);
+    }
+
+    #[named]
+    #[test]
+    fn test_read_until_split() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        bf.write_all(b"alpha,beta,gamma-delta-epsilon,zeta")
+            .unwrap();
+        bf.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = Vec::new();
+        bf.read_until(b',', &mut buf).unwrap();
+        assert_eq!(buf, b"alpha,");
+        let fields: Vec<Vec<u8>> = (&mut bf).split(b',').map(|x| x.unwrap()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                b"beta".to_vec(),
+                b"gamma-delta-epsilon".to_vec(),
+                b"zeta".to_vec()
+            ]
+        );
+        // mixed with Read
+        bf.seek(SeekFrom::Start(6)).unwrap();
+        let mut head = [0u8; 2];
+        bf.read_exact(&mut head).unwrap();
+        let mut s = String::new();
+        bf.read_line(&mut s).unwrap();
+        assert_eq!(&head, b"be");
+        assert_eq!(s, "ta,gamma-delta-epsilon,zeta");
+    }
+}
@@ -0,0 +1,58 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test31 {
+    use function_name::named;
+    use rabuf::{Algorithm, BufFile};
+    use std::io::{Seek, SeekFrom, Write};
+
+    #[named]
+    #[test]
+    fn test_checksum_vectors() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 4, 2).unwrap();
+        bf.write_all(b"xx123456789a").unwrap();
+        bf.seek(SeekFrom::Start(1)).unwrap();
+        assert_eq!(bf.checksum(2..11, Algorithm::Crc32c).unwrap(), 0xe306_9283);
+        assert_eq!(bf.checksum(2..2, Algorithm::Crc32c).unwrap(), 0);
+        assert_eq!(
+            bf.checksum(11..12, Algorithm::Fnv1a64).unwrap(),
+            0xaf63_dc4c_8601_ec8c
+        );
+        assert_eq!(
+            bf.checksum(100.., Algorithm::Fnv1a64).unwrap(),
+            0xcbf2_9ce4_8422_2325
+        );
+        assert_eq!(bf.stream_position().unwrap(), 1);
+    }
+
+    #[named]
+    #[test]
+    fn test_checksum_dirty() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 64, 4).unwrap();
+        let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+        bf.write_all(&data).unwrap();
+        bf.flush().unwrap();
+        bf.invalidate_range(0, u64::MAX, false).unwrap();
+        let crc = bf.checksum(.., Algorithm::Crc32c).unwrap();
+        let hash = bf.checksum(.., Algorithm::Fnv1a64).unwrap();
+        // the same contents with the other chunk size
+        let f = open_test_file!(concat!(function_name!(), "_2"));
+        let mut bf2 = BufFile::with_capacity("tes", f, 4096, 4).unwrap();
+        bf2.write_all(&data).unwrap();
+        assert_eq!(bf2.checksum(.., Algorithm::Crc32c).unwrap(), crc);
+        assert_eq!(bf2.checksum(.., Algorithm::Fnv1a64).unwrap(), hash);
+        // the dirty data is included
+        bf.seek(SeekFrom::Start(1000)).unwrap();
+        bf.write_all(b"dirty").unwrap();
+        let crc_dirty = bf.checksum(.., Algorithm::Crc32c).unwrap();
+        assert_ne!(crc_dirty, crc);
+        bf2.seek(SeekFrom::Start(1000)).unwrap();
+        bf2.write_all(b"dirty").unwrap();
+        bf2.flush().unwrap();
+        bf2.invalidate_range(0, u64::MAX, false).unwrap();
+        assert_eq!(bf2.checksum(.., Algorithm::Crc32c).unwrap(), crc_dirty);
+    }
+}
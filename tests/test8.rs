@@ -0,0 +1,68 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test8 {
+    use function_name::named;
+    use rabuf::{BufFile, FileSetLen};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[named]
+    #[test]
+    fn test_recycled_chunk_is_cleared() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0xAA; 64]).unwrap();
+        bf.clear().unwrap();
+        //
+        bf.set_len(20).unwrap();
+        bf.seek(SeekFrom::Start(16)).unwrap();
+        let mut br = [0u8; 4];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(br, [0xAA; 4]);
+        //
+        bf.set_len(32).unwrap();
+        bf.seek(SeekFrom::Start(16)).unwrap();
+        let mut br = [0u8; 16];
+        bf.read_exact(&mut br).unwrap();
+        assert_eq!(&br[..4], &[0xAA; 4]);
+        assert_eq!(&br[4..], &[0u8; 12]);
+    }
+
+    #[named]
+    #[test]
+    fn test_clear_many_times() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 2).unwrap();
+        for i in 0..10u8 {
+            bf.seek(SeekFrom::Start(16 * i as u64)).unwrap();
+            bf.write_all(&[i; 16]).unwrap();
+            bf.clear().unwrap();
+        }
+        bf.rewind().unwrap();
+        let mut br = Vec::new();
+        bf.read_to_end(&mut br).unwrap();
+        assert_eq!(br.len(), 160);
+        for i in 0..10u8 {
+            assert_eq!(&br[(16 * i as usize)..(16 * (i as usize + 1))], &[i; 16]);
+        }
+    }
+
+    #[cfg(feature = "buf_stats")]
+    #[named]
+    #[test]
+    fn test_stats_chunk_allocs() {
+        let f = open_test_file!(function_name!());
+        let mut bf = BufFile::with_capacity("tes", f, 16, 4).unwrap();
+        bf.write_all(&[0x55; 64]).unwrap();
+        bf.clear().unwrap();
+        bf.rewind().unwrap();
+        let mut br = [0u8; 64];
+        bf.read_exact(&mut br).unwrap();
+        //
+        let stats = bf.buf_stats();
+        let get = |nm: &str| stats.iter().find(|a| a.0 == nm).unwrap().1;
+        assert_eq!(get("BufFile.stats_chunk_allocs"), 4);
+        assert!(get("BufFile.stats_chunk_reuses") >= 3);
+    }
+}
@@ -0,0 +1,99 @@
+#[macro_use]
+mod helper;
+
+#[cfg(test)]
+mod test9 {
+    use function_name::named;
+    use rabuf::{DirectBufFile, DirectFile, FileSetLen, FileSync};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    macro_rules! open_direct_file {
+        ($fnm:expr) => {{
+            std::fs::create_dir_all(base_dir!()).unwrap();
+            let path = concat!(base_dir!(), "/", $fnm);
+            let mut opts = std::fs::OpenOptions::new();
+            opts.create(true).truncate(true).read(true).write(true);
+            // some filesystems, for example tmpfs, do not support O_DIRECT.
+            match DirectFile::open(&mut opts, path, 512) {
+                Ok(f) => f,
+                Err(_) => return,
+            }
+        }};
+    }
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[named]
+    #[test]
+    fn test_direct_file_unaligned() {
+        let f = open_test_file!(function_name!());
+        let mut df = DirectFile::from_file(f, 512).unwrap();
+        let data = test_data(1500);
+        df.seek(SeekFrom::Start(100)).unwrap();
+        df.write_all(&data).unwrap();
+        assert_eq!(df.seek(SeekFrom::End(0)).unwrap(), 1600);
+        //
+        df.seek(SeekFrom::Start(700)).unwrap();
+        df.write_all(b"xyz").unwrap();
+        //
+        let mut br = vec![0u8; 1600];
+        df.seek(SeekFrom::Start(0)).unwrap();
+        df.read_exact(&mut br).unwrap();
+        assert_eq!(&br[..100], &[0u8; 100][..]);
+        assert_eq!(&br[100..700], &data[..600]);
+        assert_eq!(&br[700..703], b"xyz");
+        assert_eq!(&br[703..], &data[603..]);
+        //
+        let f = df.into_inner();
+        assert_eq!(f.metadata().unwrap().len(), 1600);
+    }
+
+    #[named]
+    #[test]
+    fn test_direct_file_bad_block_size() {
+        let f = open_test_file!(function_name!());
+        assert!(DirectFile::from_file(f, 1000).is_err());
+        let f = open_test_file!(function_name!());
+        let df = DirectFile::from_file(f, 512).unwrap();
+        assert!(DirectBufFile::with_direct("tes", df, 256, 4).is_err());
+    }
+
+    #[named]
+    #[test]
+    fn test_direct_buf_file() {
+        let data = test_data(10_000);
+        {
+            let f = open_direct_file!(function_name!());
+            let mut bf = DirectBufFile::with_direct("tes", f, 1024, 4).unwrap();
+            bf.write_all(&data).unwrap();
+            bf.sync_all().unwrap();
+            bf.rewind().unwrap();
+            let mut br = vec![0u8; data.len()];
+            bf.read_exact(&mut br).unwrap();
+            assert_eq!(br, data);
+        }
+        let path = concat!(base_dir!(), "/", function_name!());
+        assert_eq!(std::fs::read(path).unwrap(), data);
+    }
+
+    #[named]
+    #[test]
+    fn test_direct_buf_file_set_len() {
+        let data = test_data(5000);
+        {
+            let f = open_direct_file!(function_name!());
+            let mut bf = DirectBufFile::with_direct("tes", f, 1024, 2).unwrap();
+            bf.write_all(&data).unwrap();
+            bf.set_len(3333).unwrap();
+            bf.seek(SeekFrom::End(0)).unwrap();
+            bf.write_all(b"end").unwrap();
+        }
+        let path = concat!(base_dir!(), "/", function_name!());
+        let br = std::fs::read(path).unwrap();
+        assert_eq!(br.len(), 3336);
+        assert_eq!(&br[..3333], &data[..3333]);
+        assert_eq!(&br[3333..], b"end");
+    }
+}